@@ -1,18 +1,43 @@
 use clap::{value_parser, Arg, ArgAction, ArgMatches, Command};
+use clap_complete::Shell;
+use regex::Regex;
 
 pub fn parse() -> ArgMatches {
+    command().get_matches()
+}
+
+/// Builds the command line interface definition.
+pub fn command() -> Command {
     Command::new("glp")
         .author("Hrdina Pavel <hrdina.pavel@gmail.com>")
         .about("Gitlab pipeline status for command line.")
         .arg(
             Arg::new("project")
+                .global(true)
                 .short('p')
                 .long("project")
                 .action(ArgAction::Set)
                 .value_parser(value_parser!(String)),
         )
+        .arg(
+            Arg::new("config")
+                .global(true)
+                .long("config")
+                .help("Config file to use instead of the default one")
+                .action(ArgAction::Set)
+                .value_parser(value_parser!(String)),
+        )
+        .arg(
+            Arg::new("profile")
+                .global(true)
+                .long("profile")
+                .help("Gitlab instance profile from config file")
+                .action(ArgAction::Set)
+                .value_parser(value_parser!(String)),
+        )
         .arg(
             Arg::new("limit")
+                .global(true)
                 .short('l')
                 .long("limit")
                 .action(ArgAction::Set)
@@ -21,9 +46,729 @@ pub fn parse() -> ArgMatches {
         )
         .arg(
             Arg::new("finished")
+                .global(true)
                 .short('f')
                 .long("finished")
                 .action(ArgAction::SetTrue),
         )
-        .get_matches()
+        .arg(
+            Arg::new("collapse-success")
+                .global(true)
+                .long("collapse-success")
+                .help("Render successful stages as a single summary line")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("failed-only")
+                .global(true)
+                .long("failed-only")
+                .help("Show failed pipelines, stages and jobs only")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("tags-only")
+                .global(true)
+                .long("tags-only")
+                .help("Show tag pipelines only")
+                .conflicts_with("branches-only")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("branches-only")
+                .global(true)
+                .long("branches-only")
+                .help("Show branch pipelines only")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("stage")
+                .global(true)
+                .short('s')
+                .long("stage")
+                .help("Show only stages with given names (comma separated)")
+                .action(ArgAction::Append)
+                .value_delimiter(',')
+                .value_parser(value_parser!(String)),
+        )
+        .arg(
+            Arg::new("job")
+                .global(true)
+                .short('j')
+                .long("job")
+                .help("Show only jobs with names matching given regex")
+                .action(ArgAction::Set)
+                .value_parser(|s: &str| Regex::new(s)),
+        )
+        .arg(
+            Arg::new("depth")
+                .global(true)
+                .short('d')
+                .long("depth")
+                .help("How deep the rendered tree is")
+                .action(ArgAction::Set)
+                .value_parser(["pipelines", "stages", "jobs"])
+                .default_value("jobs"),
+        )
+        .arg(
+            Arg::new("sort")
+                .global(true)
+                .long("sort")
+                .help("Order of pipelines")
+                .action(ArgAction::Set)
+                .value_parser(["id", "updated", "duration", "status"])
+                .default_value("id"),
+        )
+        .arg(
+            Arg::new("reverse")
+                .global(true)
+                .long("reverse")
+                .help("Reverse order of pipelines")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("sort-jobs")
+                .global(true)
+                .long("sort-jobs")
+                .help("Order of jobs within a stage")
+                .action(ArgAction::Set)
+                .value_parser(["status", "duration", "name"]),
+        )
+        .arg(
+            Arg::new("variables")
+                .global(true)
+                .long("variables")
+                .help("Show variables pipelines were triggered with")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("excerpts")
+                .global(true)
+                .long("excerpts")
+                .help("Show the most relevant log lines of failed jobs")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("next-schedules")
+                .global(true)
+                .long("next-schedules")
+                .help("Show upcoming scheduled pipelines below the listing")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("interactive")
+                .global(true)
+                .long("interactive")
+                .help("Offer to play manual jobs below the listing")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("proxy")
+                .global(true)
+                .long("proxy")
+                .help("Proxy URL (http://, https:// or socks5://)")
+                .action(ArgAction::Set)
+                .value_parser(value_parser!(String)),
+        )
+        .arg(
+            Arg::new("ca-cert")
+                .global(true)
+                .long("ca-cert")
+                .help("PEM file with additional trusted CA certificate(s)")
+                .action(ArgAction::Set)
+                .value_parser(value_parser!(String)),
+        )
+        .arg(
+            Arg::new("client-cert")
+                .global(true)
+                .long("client-cert")
+                .help("PEM file with client certificate (mutual TLS)")
+                .requires("client-key")
+                .action(ArgAction::Set)
+                .value_parser(value_parser!(String)),
+        )
+        .arg(
+            Arg::new("client-key")
+                .global(true)
+                .long("client-key")
+                .help("PEM file with client certificate private key (PKCS#8)")
+                .requires("client-cert")
+                .action(ArgAction::Set)
+                .value_parser(value_parser!(String)),
+        )
+        .arg(
+            Arg::new("insecure")
+                .global(true)
+                .long("insecure")
+                .help("Skip TLS certificate verification (dangerous)")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("urls")
+                .global(true)
+                .long("urls")
+                .help("Show web URLs of pipelines and jobs")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("show-iid")
+                .global(true)
+                .long("iid")
+                .help("Show project scoped pipeline IDs (as in Gitlab UI) - same as --id-format iid")
+                .conflicts_with("id-format")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("id-format")
+                .global(true)
+                .long("id-format")
+                .help("Pipeline identifier to show - global ID, project scoped IID or both")
+                .action(ArgAction::Set)
+                .value_parser(["id", "iid", "both"])
+                .default_value("id"),
+        )
+        .arg(
+            Arg::new("output")
+                .global(true)
+                .short('o')
+                .long("output")
+                .help("Output format - tree, table, compact, csv, json, jsonl (streamed) or yaml")
+                .action(ArgAction::Set)
+                .value_parser(["tree", "table", "compact", "csv", "json", "jsonl", "yaml"])
+                .default_value("tree"),
+        )
+        .arg(
+            Arg::new("jobs")
+                .global(true)
+                .long("jobs")
+                .help("Output jobs instead of pipelines (csv output only)")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("sparkline")
+                .global(true)
+                .long("sparkline")
+                .help("Show durations of recent pipelines of the same ref as a sparkline")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("empty-stages")
+                .global(true)
+                .long("empty-stages")
+                .help("Show stages declared in CI config which have no jobs")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("iso-times")
+                .global(true)
+                .long("iso-times")
+                .help("Show times as RFC 3339 timestamps (UTC) instead of relative ones")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("graphql")
+                .global(true)
+                .long("graphql")
+                .help("Fetch stages and jobs via GraphQL API (single request per pipeline)")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("ascii")
+                .global(true)
+                .long("ascii")
+                .help("Use ASCII characters only (also spells statuses out)")
+                .action(ArgAction::SetTrue),
+        )
+        .subcommand(
+            Command::new("mr")
+                .about("Shows pipelines of a merge request")
+                .arg(
+                    Arg::new("iid")
+                        .help("Merge request IID")
+                        .required_unless_present("current")
+                        .value_parser(value_parser!(u64)),
+                )
+                .arg(
+                    Arg::new("current")
+                        .long("current")
+                        .help("Use open merge request of the current git branch")
+                        .conflicts_with("iid")
+                        .action(ArgAction::SetTrue),
+                ),
+        )
+        .subcommand(
+            Command::new("commit")
+                .about("Shows pipelines of a commit")
+                .arg(
+                    Arg::new("sha")
+                        .help("Commit SHA (or branch/tag name)")
+                        .required(true)
+                        .value_parser(value_parser!(String)),
+                ),
+        )
+        .subcommand(
+            Command::new("schedules")
+                .about("Lists pipeline schedules")
+                .subcommand(
+                    Command::new("run")
+                        .about("Runs pipeline schedule immediately and follows the pipeline")
+                        .arg(
+                            Arg::new("id")
+                                .help("Pipeline schedule ID")
+                                .required(true)
+                                .value_parser(value_parser!(u64)),
+                        ),
+                ),
+        )
+        .subcommand(
+            Command::new("envs")
+                .about("Lists environments with their latest deployments")
+                .subcommand(
+                    Command::new("stop").about("Stops an environment").arg(
+                        Arg::new("name")
+                            .help("Environment name")
+                            .required(true)
+                            .value_parser(value_parser!(String)),
+                    ),
+                ),
+        )
+        .subcommand(
+            Command::new("deployments")
+                .about("Manages deployments to protected environments")
+                .subcommand_required(true)
+                .subcommand(approval_command("approve", "Approves a blocked deployment"))
+                .subcommand(approval_command("reject", "Rejects a blocked deployment")),
+        )
+        .subcommand(
+            Command::new("jobs")
+                .about("Lists recent jobs across all pipelines")
+                .arg(
+                    Arg::new("status")
+                        .long("status")
+                        .visible_alias("scope")
+                        .help("Show only jobs with given status(es)")
+                        .action(ArgAction::Append)
+                        .value_delimiter(',')
+                        .value_parser([
+                            "created",
+                            "pending",
+                            "running",
+                            "failed",
+                            "success",
+                            "canceled",
+                            "skipped",
+                            "waiting_for_resource",
+                            "manual",
+                        ]),
+                ),
+        )
+        .subcommand(Command::new("releases").about("Lists recent releases with their pipelines"))
+        .subcommand(
+            Command::new("projects")
+                .about("Lists projects you are member of")
+                .arg(
+                    Arg::new("search")
+                        .long("search")
+                        .help("Show only projects matching the term")
+                        .action(ArgAction::Set)
+                        .value_parser(value_parser!(String)),
+                )
+                .arg(
+                    Arg::new("pick")
+                        .long("pick")
+                        .help("Choose a project and write it into .glp file")
+                        .action(ArgAction::SetTrue),
+                ),
+        )
+        .subcommand(
+            Command::new("run")
+                .about("Creates a new pipeline")
+                .arg(
+                    Arg::new("ref")
+                        .long("ref")
+                        .help(
+                            "Branch or tag to run the pipeline for (current git branch by default)",
+                        )
+                        .value_parser(value_parser!(String)),
+                )
+                .arg(
+                    Arg::new("vars-file")
+                        .long("vars-file")
+                        .help("Pipeline variables file (dotenv or .json)")
+                        .value_parser(value_parser!(String)),
+                )
+                .arg(
+                    Arg::new("follow")
+                        .long("follow")
+                        .help("Follow the pipeline and exit with its result")
+                        .action(ArgAction::SetTrue),
+                ),
+        )
+        .subcommand(
+            Command::new("rerun")
+                .about("Runs a pipeline again (new pipeline for the same ref and variables)")
+                .arg(
+                    Arg::new("pipeline-id")
+                        .help("Pipeline ID")
+                        .required(true)
+                        .value_parser(value_parser!(String)),
+                )
+                .arg(
+                    Arg::new("retry")
+                        .long("retry")
+                        .help("Retry failed and canceled jobs of the pipeline instead")
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("follow")
+                        .long("follow")
+                        .help("Follow the pipeline and exit with its result")
+                        .action(ArgAction::SetTrue),
+                ),
+        )
+        .subcommand(
+            Command::new("retry")
+                .about("Retries jobs of a pipeline")
+                .arg(
+                    Arg::new("all-failed")
+                        .long("all-failed")
+                        .help("Retry all failed jobs (except allowed to fail ones)")
+                        .required(true)
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("pipeline")
+                        .long("pipeline")
+                        .help("Pipeline ID (latest pipeline by default)")
+                        .value_parser(value_parser!(String)),
+                )
+                .arg(
+                    Arg::new("follow")
+                        .long("follow")
+                        .help("Follow the pipeline and exit with its result")
+                        .action(ArgAction::SetTrue),
+                ),
+        )
+        .subcommand(
+            Command::new("cancel")
+                .about("Cancels pipelines")
+                .arg(
+                    Arg::new("redundant")
+                        .long("redundant")
+                        .help("Cancel running/pending pipelines of the ref except the newest one")
+                        .required(true)
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("ref")
+                        .long("ref")
+                        .help("Branch or tag of the pipelines (current git branch by default)")
+                        .value_parser(value_parser!(String)),
+                ),
+        )
+        .subcommand(
+            Command::new("lint").about("Validates CI config file").arg(
+                Arg::new("path")
+                    .help("Path to CI config file (.gitlab-ci.yml by default)")
+                    .value_parser(value_parser!(String)),
+            ),
+        )
+        .subcommand(
+            Command::new("erase")
+                .about("Erases job log and artifacts")
+                .arg(
+                    Arg::new("job-id")
+                        .help("Job ID")
+                        .required(true)
+                        .value_parser(value_parser!(u64)),
+                )
+                .arg(
+                    Arg::new("yes")
+                        .short('y')
+                        .long("yes")
+                        .help("Don't ask for confirmation")
+                        .action(ArgAction::SetTrue),
+                ),
+        )
+        .subcommand(
+            Command::new("artifacts")
+                .about("Manages job artifacts")
+                .subcommand_required(true)
+                .subcommand(
+                    Command::new("report")
+                        .about("Reports artifacts size of recent pipelines (see --limit)"),
+                )
+                .subcommand(
+                    Command::new("keep")
+                        .about("Keeps job artifacts past their expiry")
+                        .arg(
+                            Arg::new("job-id")
+                                .help("Job ID")
+                                .required(true)
+                                .value_parser(value_parser!(u64)),
+                        ),
+                ),
+        )
+        .subcommand(
+            Command::new("logs")
+                .about("Downloads job logs of a pipeline")
+                .arg(
+                    Arg::new("pipeline")
+                        .long("pipeline")
+                        .help("Pipeline ID (latest pipeline by default)")
+                        .value_parser(value_parser!(String)),
+                )
+                .arg(
+                    Arg::new("failed")
+                        .long("failed")
+                        .help("Download logs of failed jobs only")
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("dest")
+                        .long("dest")
+                        .help("Destination directory")
+                        .value_parser(value_parser!(String))
+                        .default_value("."),
+                ),
+        )
+        .subcommand(
+            Command::new("grep")
+                .about("Searches job logs of a pipeline (see --failed-only)")
+                .arg(
+                    Arg::new("pattern")
+                        .help("Regex to search for")
+                        .required(true)
+                        .value_parser(|s: &str| Regex::new(s)),
+                )
+                .arg(
+                    Arg::new("pipeline")
+                        .long("pipeline")
+                        .help("Pipeline ID (latest pipeline by default)")
+                        .value_parser(value_parser!(String)),
+                ),
+        )
+        .subcommand(
+            Command::new("stats")
+                .about("Shows CI health statistics (or duration statistics of jobs given by --job param)")
+                .arg(
+                    Arg::new("histogram")
+                        .long("histogram")
+                        .help("Show histogram of job durations")
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("stages")
+                        .long("stages")
+                        .help("Show average and p95 duration of each stage")
+                        .conflicts_with("histogram")
+                        .action(ArgAction::SetTrue),
+                ),
+        )
+        .subcommand(
+            Command::new("watch")
+                .about("Redraws recent pipelines periodically (until interrupted)")
+                .arg(
+                    Arg::new("fullscreen")
+                        .long("fullscreen")
+                        .help("Take over the terminal and show status bar")
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("notify")
+                        .long("notify")
+                        .help("Alert on status changes (see [notify] config section)")
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(timeout_arg()),
+        )
+        .subcommand(
+            Command::new("report")
+                .about("Renders recent pipelines by Tera template")
+                .arg(
+                    Arg::new("template")
+                        .long("template")
+                        .help("Template file (HTML is escaped for .html files)")
+                        .required(true)
+                        .value_parser(value_parser!(String)),
+                ),
+        )
+        .subcommand(
+            Command::new("summary")
+                .about("Prints status counts of pipelines and the worst status per ref"),
+        )
+        .subcommand(
+            Command::new("status")
+                .about("Prints status of the latest pipeline (exit code 0 for success, 1 for failure, 3 otherwise)")
+                .arg(
+                    Arg::new("ref")
+                        .long("ref")
+                        .help("Git ref (branch or tag) of the pipeline")
+                        .action(ArgAction::Set)
+                        .value_parser(value_parser!(String)),
+                ),
+        )
+        .subcommand(
+            Command::new("wait")
+                .about("Follows the latest pipeline (or just jobs whose whole name matches --job regex) until it finishes (exit code as for status)")
+                .arg(
+                    Arg::new("ref")
+                        .long("ref")
+                        .help("Git ref (branch or tag) of the pipeline")
+                        .action(ArgAction::Set)
+                        .value_parser(value_parser!(String)),
+                )
+                .arg(timeout_arg()),
+        )
+        .subcommand(
+            Command::new("badge")
+                .about("Writes SVG badge with status of the latest pipeline")
+                .arg(
+                    Arg::new("ref")
+                        .long("ref")
+                        .help("Git ref (branch or tag) of the pipeline")
+                        .action(ArgAction::Set)
+                        .value_parser(value_parser!(String)),
+                )
+                .arg(
+                    Arg::new("coverage")
+                        .long("coverage")
+                        .help("Show test coverage instead of status")
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("out")
+                        .long("out")
+                        .help("Output SVG file")
+                        .action(ArgAction::Set)
+                        .value_parser(value_parser!(String))
+                        .default_value("badge.svg"),
+                ),
+        )
+        .subcommand(
+            Command::new("check-budgets")
+                .about("Checks pipeline and job durations against budgets from config file")
+                .arg(
+                    Arg::new("pipeline-id")
+                        .help("Pipeline ID")
+                        .required(true)
+                        .value_parser(value_parser!(String)),
+                ),
+        )
+        .subcommand(
+            Command::new("security")
+                .about("Summarizes security reports of a pipeline")
+                .arg(
+                    Arg::new("pipeline-id")
+                        .help("Pipeline ID")
+                        .required(true)
+                        .value_parser(value_parser!(String)),
+                ),
+        )
+        .subcommand(
+            Command::new("quality")
+                .about("Summarizes code quality report of a pipeline")
+                .arg(
+                    Arg::new("pipeline-id")
+                        .help("Pipeline ID")
+                        .required(true)
+                        .value_parser(value_parser!(String)),
+                )
+                .arg(
+                    Arg::new("baseline")
+                        .long("baseline")
+                        .help("Show only issues new compared to this pipeline")
+                        .value_parser(value_parser!(String)),
+                ),
+        )
+        .subcommand(
+            Command::new("diff")
+                .about("Compares two pipelines")
+                .arg(
+                    Arg::new("tests")
+                        .long("tests")
+                        .help("Compare test reports")
+                        .required(true)
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("a")
+                        .help("Pipeline ID to compare against")
+                        .required(true)
+                        .value_parser(value_parser!(String)),
+                )
+                .arg(
+                    Arg::new("b")
+                        .help("Pipeline ID to compare")
+                        .required(true)
+                        .value_parser(value_parser!(String)),
+                ),
+        )
+        .subcommand(
+            Command::new("train").about("Shows merge train queue").arg(
+                Arg::new("ref")
+                    .long("ref")
+                    .help("Target branch of the merge train")
+                    .value_parser(value_parser!(String)),
+            ),
+        )
+        .subcommand(
+            Command::new("completions")
+                .about("Prints shell completion script (with project aliases from config file)")
+                .arg(
+                    Arg::new("shell")
+                        .help("Shell to complete for")
+                        .required(true)
+                        .value_parser(value_parser!(Shell)),
+                ),
+        )
+}
+
+/// `--timeout` param shared by wait modes.
+fn timeout_arg() -> Arg {
+    Arg::new("timeout")
+        .long("timeout")
+        .help("Give up after given time like \"30m\" (exit code 4)")
+        .action(ArgAction::Set)
+        .value_parser(humantime::parse_duration)
+}
+
+/// Approval subcommand of `deployments`.
+fn approval_command(name: &'static str, about: &'static str) -> Command {
+    Command::new(name)
+        .about(about)
+        .arg(
+            Arg::new("deployment-id")
+                .help("Deployment ID")
+                .required(true)
+                .value_parser(value_parser!(String)),
+        )
+        .arg(
+            Arg::new("comment")
+                .long("comment")
+                .help("Approval comment")
+                .value_parser(value_parser!(String)),
+        )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mr_iid_does_not_clash_with_iid_flag() {
+        let args = command().try_get_matches_from(["glp", "mr", "5"]).unwrap();
+        let (_, mr_args) = args.subcommand().unwrap();
+
+        assert_eq!(Some(&5), mr_args.get_one::<u64>("iid"));
+        assert_eq!(Some(&false), args.get_one::<bool>("show-iid"));
+
+        let args = command()
+            .try_get_matches_from(["glp", "mr", "5", "--iid"])
+            .unwrap();
+        let (_, mr_args) = args.subcommand().unwrap();
+
+        assert_eq!(Some(&5), mr_args.get_one::<u64>("iid"));
+        assert_eq!(Some(&true), mr_args.get_one::<bool>("show-iid"));
+        assert_eq!(Some(&true), args.get_one::<bool>("show-iid"));
+    }
 }
@@ -0,0 +1,137 @@
+use crate::gitlab::{self, Gitlab};
+use colored::*;
+use futures::future::join_all;
+use json::JsonValue;
+use std::collections::HashMap;
+
+const REPORT_TOP: usize = 10;
+
+/// Formats byte size like "14.2 MB".
+pub fn format_size(bytes: u64) -> String {
+    let units = ["B", "kB", "MB", "GB", "TB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+
+    while size >= 1000.0 && unit < units.len() - 1 {
+        size /= 1000.0;
+        unit += 1;
+    }
+
+    match unit {
+        0 => format!("{} {}", bytes, units[0]),
+        _ => format!("{:.1} {}", size, units[unit]),
+    }
+}
+
+/// Sums sizes of all job artifacts.
+pub fn artifacts_size(job: &JsonValue) -> u64 {
+    job["artifacts"]
+        .members()
+        .filter_map(|a| a["size"].as_u64())
+        .sum()
+}
+
+/// Downloads report artifacts (e.g. "sast", "codequality")
+/// of all pipeline jobs. Returns pairs of report type and
+/// parsed report.
+/// Report has to be part of the job artifacts archive under
+/// its file name (e.g. `gl-sast-report.json`).
+pub async fn fetch_reports(
+    gitlab: &Gitlab,
+    pipeline_id: &str,
+    file_types: &[&str],
+) -> gitlab::Result<Vec<(String, JsonValue)>> {
+    let jobs = gitlab
+        .get_all(&format!("pipelines/{}/jobs", pipeline_id))
+        .await?;
+    let mut reports = vec![];
+
+    for job in jobs.members() {
+        for artifact in job["artifacts"].members() {
+            let file_type = artifact["file_type"].as_str().unwrap_or("");
+
+            if !file_types.contains(&file_type) {
+                continue;
+            }
+
+            let report = gitlab
+                .get(&format!(
+                    "jobs/{}/artifacts/{}",
+                    job["id"],
+                    artifact["filename"].as_str().unwrap_or("")
+                ))
+                .await?;
+
+            reports.push((file_type.to_string(), report));
+        }
+    }
+
+    Ok(reports)
+}
+
+/// Prevents job artifacts from being deleted when they
+/// expire.
+pub async fn keep(gitlab: &Gitlab, id: u64) -> gitlab::Result<()> {
+    let job = gitlab
+        .post(&format!("jobs/{}/artifacts/keep", id), &[])
+        .await?;
+
+    match job["id"].is_null() {
+        true => Err(format!("Cannot keep artifacts: {}", job["message"]).into()),
+        false => {
+            println!("Artifacts of job {} ({}) are kept.", id, job["name"]);
+            Ok(())
+        }
+    }
+}
+
+/// Prints total artifacts size of jobs of recent pipelines
+/// and the largest producers (by job name).
+pub async fn report(gitlab: &Gitlab, limit: u8) -> gitlab::Result<()> {
+    let pipelines = gitlab
+        .get_keyset("pipelines?order_by=id&sort=desc", limit as usize)
+        .await?;
+    let jobs = join_all(
+        pipelines
+            .members()
+            .map(|p| async move { gitlab.get_all(&format!("pipelines/{}/jobs", p["id"])).await }),
+    )
+    .await;
+
+    // Job name -> (total size, number of jobs).
+    let mut producers: HashMap<String, (u64, usize)> = HashMap::new();
+
+    for pipeline_jobs in jobs {
+        for job in pipeline_jobs?.members() {
+            let size = artifacts_size(job);
+
+            if size > 0 {
+                let producer = producers.entry(job["name"].to_string()).or_default();
+                producer.0 += size;
+                producer.1 += 1;
+            }
+        }
+    }
+
+    let total: u64 = producers.values().map(|p| p.0).sum();
+    let mut producers = producers.into_iter().collect::<Vec<_>>();
+    producers.sort_by_key(|p| std::cmp::Reverse(p.1 .0));
+
+    println!(
+        "{} in last {} pipelines",
+        format_size(total).bold(),
+        pipelines.len()
+    );
+
+    for (name, (size, count)) in producers.iter().take(REPORT_TOP) {
+        println!(
+            "{:>10}  {} ({} {})",
+            format_size(*size),
+            name,
+            count,
+            if *count == 1 { "job" } else { "jobs" }
+        );
+    }
+
+    Ok(())
+}
@@ -0,0 +1,106 @@
+use crate::gitlab::{self, Gitlab};
+use std::fs;
+
+/// Approximate width of a character in the badge font
+/// (11px Verdana).
+const CHAR_WIDTH: usize = 7;
+const PADDING: usize = 10;
+
+/// Picks badge color for pipeline status.
+fn status_color(status: &str) -> &'static str {
+    match status {
+        "success" => "#4c1",
+        "failed" => "#e05d44",
+        "running" | "pending" => "#dfb317",
+        _ => "#9f9f9f",
+    }
+}
+
+/// Picks badge color for coverage percentage.
+fn coverage_color(coverage: f64) -> &'static str {
+    match coverage {
+        c if c >= 90.0 => "#4c1",
+        c if c >= 75.0 => "#a3c51c",
+        c if c >= 50.0 => "#dfb317",
+        _ => "#e05d44",
+    }
+}
+
+/// Renders shields-style SVG badge like "pipeline | success".
+fn render(label: &str, value: &str, color: &str) -> String {
+    let label_width = label.chars().count() * CHAR_WIDTH + PADDING;
+    let value_width = value.chars().count() * CHAR_WIDTH + PADDING;
+    let width = label_width + value_width;
+
+    format!(
+        r##"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="20" role="img" aria-label="{label}: {value}">
+  <linearGradient id="s" x2="0" y2="100%"><stop offset="0" stop-color="#bbb" stop-opacity=".1"/><stop offset="1" stop-opacity=".1"/></linearGradient>
+  <clipPath id="r"><rect width="{width}" height="20" rx="3" fill="#fff"/></clipPath>
+  <g clip-path="url(#r)">
+    <rect width="{label_width}" height="20" fill="#555"/>
+    <rect x="{label_width}" width="{value_width}" height="20" fill="{color}"/>
+    <rect width="{width}" height="20" fill="url(#s)"/>
+  </g>
+  <g fill="#fff" text-anchor="middle" font-family="Verdana,Geneva,DejaVu Sans,sans-serif" font-size="11">
+    <text x="{label_x}" y="14">{label}</text>
+    <text x="{value_x}" y="14">{value}</text>
+  </g>
+</svg>
+"##,
+        width = width,
+        label_width = label_width,
+        value_width = value_width,
+        color = color,
+        label = label,
+        value = value,
+        label_x = label_width / 2,
+        value_x = label_width + value_width / 2,
+    )
+}
+
+/// Writes SVG badge with status (or coverage) of the latest
+/// pipeline (of given ref) into `out` file.
+pub async fn write(
+    gitlab: &Gitlab,
+    git_ref: Option<&String>,
+    coverage: bool,
+    out: &str,
+) -> gitlab::Result<()> {
+    let mut path = "pipelines?per_page=1".to_string();
+
+    if let Some(git_ref) = git_ref {
+        path.push_str(&format!("&ref={}", gitlab::encode(git_ref)));
+    }
+
+    let pipelines = gitlab.get(&path).await?;
+
+    if pipelines.is_empty() {
+        return Err("No pipeline found.".into());
+    }
+
+    let svg = match coverage {
+        true => {
+            // Coverage is not part of the listing.
+            let details = gitlab
+                .get(&format!("pipelines/{}", pipelines[0]["id"]))
+                .await?;
+
+            match details["coverage"]
+                .as_str()
+                .and_then(|c| c.parse::<f64>().ok())
+            {
+                Some(c) => render("coverage", &format!("{:.1}%", c), coverage_color(c)),
+                None => render("coverage", "unknown", "#9f9f9f"),
+            }
+        }
+        false => {
+            let status = pipelines[0]["status"].as_str().unwrap_or("unknown");
+            render("pipeline", status, status_color(status))
+        }
+    };
+
+    fs::write(out, svg)?;
+    println!("Badge written into {}.", out);
+
+    Ok(())
+}
@@ -0,0 +1,80 @@
+use crate::config::BudgetsConfig;
+use crate::gitlab::{self, Gitlab};
+use crate::time;
+use colored::*;
+use humantime::parse_duration;
+use std::time::Duration;
+
+/// Parses budget like "10m 30s".
+fn parse(budget: &str) -> gitlab::Result<Duration> {
+    parse_duration(budget)
+        .map_err(|e| format!("Invalid duration budget \"{}\": {}", budget, e).into())
+}
+
+/// Checks pipeline and its jobs durations against configured
+/// budgets. Violations are listed and result in an error.
+/// Produces output like:
+/// `test: 12m 5s > 10m`.
+pub async fn check(
+    gitlab: &Gitlab,
+    pipeline_id: &str,
+    budgets: &BudgetsConfig,
+) -> gitlab::Result<()> {
+    let mut violations = vec![];
+
+    if let Some(budget) = &budgets.pipeline {
+        let budget = parse(budget)?;
+        let pipeline = gitlab.get(&format!("pipelines/{}", pipeline_id)).await?;
+
+        if pipeline["id"].is_null() {
+            return Err(format!("Pipeline {} not found.", pipeline_id).into());
+        }
+
+        if let Some(duration) = pipeline["duration"].as_u64() {
+            if Duration::from_secs(duration) > budget {
+                violations.push((
+                    "pipeline".to_string(),
+                    Duration::from_secs(duration),
+                    budget,
+                ));
+            }
+        }
+    }
+
+    if !budgets.jobs.is_empty() {
+        let jobs = gitlab
+            .get_all(&format!("pipelines/{}/jobs", pipeline_id))
+            .await?;
+
+        for job in jobs.members() {
+            let name = job["name"].as_str().unwrap_or("");
+
+            if let (Some(budget), Some(duration)) =
+                (budgets.jobs.get(name), job["duration"].as_f64())
+            {
+                let budget = parse(budget)?;
+                let duration = Duration::from_secs(duration as u64);
+
+                if duration > budget {
+                    violations.push((name.to_string(), duration, budget));
+                }
+            }
+        }
+    }
+
+    if violations.is_empty() {
+        println!("{}", "All durations within budgets.".green());
+        return Ok(());
+    }
+
+    for (name, duration, budget) in violations.iter() {
+        println!(
+            "{}: {} > {}",
+            name.red(),
+            time::duration(*duration),
+            time::duration(*budget)
+        );
+    }
+
+    Err(format!("{} duration budget(s) exceeded.", violations.len()).into())
+}
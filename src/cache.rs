@@ -0,0 +1,42 @@
+use crate::gitlab::{self, Gitlab};
+use directories::ProjectDirs;
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+
+/// Returns path to project path → ID cache file.
+fn path() -> Option<PathBuf> {
+    ProjectDirs::from("", "", "glp").map(|dirs| dirs.cache_dir().join("projects.toml"))
+}
+
+/// Resolves project path ("group/project") to its numeric ID.
+/// Resolved IDs are cached on disk (per Gitlab instance) so the
+/// lookup is made just once. Cache is best effort - failing to
+/// read or write it is not an error.
+pub async fn project_id(gitlab: &Gitlab, project: &str) -> gitlab::Result<String> {
+    let key = format!("{}/{}", gitlab.url(), project.trim());
+    let mut cache: HashMap<String, u64> = path()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|content| toml::from_str(&content).ok())
+        .unwrap_or_default();
+
+    if let Some(id) = cache.get(&key) {
+        return Ok(id.to_string());
+    }
+
+    let id = match gitlab.project().await?["id"].as_u64() {
+        Some(id) => id,
+        None => return Err(format!("Project {} not found.", project.trim()).into()),
+    };
+
+    cache.insert(key, id);
+
+    if let (Some(path), Ok(content)) = (path(), toml::to_string(&cache)) {
+        if let Some(dir) = path.parent() {
+            let _ = fs::create_dir_all(dir);
+        }
+        let _ = fs::write(path, content);
+    }
+
+    Ok(id.to_string())
+}
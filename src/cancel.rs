@@ -0,0 +1,60 @@
+use crate::gitlab::{self, Gitlab};
+use crate::mr;
+use crate::watch;
+use futures::future::join_all;
+
+/// Number of recent pipelines checked for redundant ones.
+const REDUNDANT_PIPELINES: usize = 100;
+
+/// Cancels running/pending pipelines of given ref (current git
+/// branch by default) except the newest pipeline - same as
+/// Gitlab's auto-cancel of redundant pipelines.
+pub async fn redundant(gitlab: &Gitlab, git_ref: Option<&String>) -> gitlab::Result<()> {
+    let git_ref = match git_ref {
+        Some(git_ref) => git_ref.to_owned(),
+        None => mr::current_branch()?,
+    };
+    let pipelines = gitlab
+        .get_paged(
+            &format!(
+                "pipelines?ref={}&order_by=id&sort=desc",
+                gitlab::encode(&git_ref)
+            ),
+            REDUNDANT_PIPELINES,
+        )
+        .await?;
+    let redundant = pipelines
+        .members()
+        .skip(1)
+        .filter(|p| watch::is_active(p["status"].as_str().unwrap_or("")))
+        .collect::<Vec<_>>();
+
+    if redundant.is_empty() {
+        println!("No redundant pipelines of {}.", git_ref);
+        return Ok(());
+    }
+
+    let canceled = join_all(redundant.iter().map(|pipeline| async move {
+        gitlab
+            .post(&format!("pipelines/{}/cancel", pipeline["id"]), &[])
+            .await
+    }))
+    .await;
+
+    for (pipeline, canceled) in redundant.iter().zip(canceled) {
+        let canceled = canceled?;
+
+        match canceled["status"].as_str() {
+            Some(status) => println!("Pipeline {} {}.", pipeline["id"], status),
+            None => {
+                return Err(format!(
+                    "Cannot cancel pipeline {}: {}",
+                    pipeline["id"], canceled["message"]
+                )
+                .into())
+            }
+        }
+    }
+
+    Ok(())
+}
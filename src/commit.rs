@@ -0,0 +1,76 @@
+use crate::gitlab::{self, Gitlab};
+use crate::job::Job;
+use crate::pipeline::Pipeline;
+use crate::stage::Stage;
+use crate::Label;
+
+/// Resolves commit reference (short SHA, branch, tag) to
+/// the full commit SHA.
+pub async fn resolve(gitlab: &Gitlab, sha: &str) -> gitlab::Result<String> {
+    let commit = gitlab
+        .get(&format!("repository/commits/{}", gitlab::encode(sha)))
+        .await?;
+
+    match commit["id"].as_str() {
+        Some(id) => Ok(id.to_string()),
+        None => Err(format!("Commit \"{}\" not found.", sha).into()),
+    }
+}
+
+/// Adds commit statuses which are not pipeline jobs (reported
+/// by external services) to their pipelines as "external"
+/// stage.
+pub async fn add_statuses(gitlab: &Gitlab, sha: &str, pips: &mut [Pipeline]) -> gitlab::Result<()> {
+    let statuses = gitlab
+        .get_all(&format!("repository/commits/{}/statuses?all=true", sha))
+        .await?;
+
+    for pip in pips.iter_mut() {
+        let mut jobs = vec![];
+
+        let job_names = pip
+            .stages
+            .iter()
+            .flat_map(|s| s.jobs.iter().map(|j| j.name.0.clone()))
+            .collect::<Vec<String>>();
+
+        for status in statuses.members() {
+            let name = status["name"].as_str().unwrap_or("");
+
+            // Skip statuses of other pipelines and of ordinary
+            // pipeline jobs.
+            if status["pipeline_id"].as_usize() != pip.id.0.parse().ok()
+                || job_names.iter().any(|n| n == name)
+            {
+                continue;
+            }
+
+            jobs.push(Job {
+                id: status["id"].as_usize().unwrap().to_string(),
+                name: Label(name.to_string()),
+                web_url: status["target_url"].as_str().unwrap_or("").to_string(),
+                status: status["status"].as_str().unwrap().to_string(),
+                stage: "external".to_string(),
+                started_at: status["started_at"].as_str().map(|s| s.to_string()),
+                duration: None,
+                coverage: None,
+                excerpt: vec![],
+                downstream: None,
+                environment: None,
+                artifacts_size: None,
+                blocked: None,
+                needs: vec![],
+            });
+        }
+
+        if !jobs.is_empty() {
+            pip.stages.push(Stage {
+                name: Label("external".to_string()),
+                jobs,
+                declared_only: false,
+            });
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,75 @@
+use crate::args;
+use crate::config::Config;
+use clap::builder::{PossibleValue, StringValueParser, TypedValueParser};
+use clap::{Arg, Command};
+use clap_complete::Shell;
+use std::ffi::OsStr;
+use std::io::{self, Write};
+
+/// Accepts any project but offers given aliases as possible
+/// values - so completions suggest them while IDs and paths
+/// still work.
+#[derive(Clone)]
+struct Aliases(Vec<String>);
+
+impl TypedValueParser for Aliases {
+    type Value = String;
+
+    fn parse_ref(
+        &self,
+        cmd: &Command,
+        arg: Option<&Arg>,
+        value: &OsStr,
+    ) -> Result<Self::Value, clap::Error> {
+        StringValueParser::new().parse_ref(cmd, arg, value)
+    }
+
+    fn possible_values(&self) -> Option<Box<dyn Iterator<Item = PossibleValue> + '_>> {
+        Some(Box::new(
+            self.0.iter().map(|alias| PossibleValue::new(alias.clone())),
+        ))
+    }
+}
+
+/// Writes completion script for given shell - `-p` completes
+/// project aliases (`[alias]` config section).
+fn generate(shell: Shell, config: &Config, out: &mut dyn Write) {
+    let mut aliases: Vec<String> = config.alias.keys().cloned().collect();
+    aliases.sort();
+
+    let mut command = args::command();
+
+    if !aliases.is_empty() {
+        command = command.mut_arg("project", |arg| {
+            arg.value_parser(Aliases(aliases))
+                .hide_possible_values(true)
+        });
+    }
+
+    clap_complete::generate(shell, &mut command, "glp", out);
+}
+
+/// Prints completion script for given shell.
+pub fn print(shell: Shell, config: &Config) {
+    generate(shell, config, &mut io::stdout());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn project_aliases_are_completed() {
+        let mut config = Config::default();
+        config
+            .alias
+            .insert("backend".to_string(), "team/backend-api".to_string());
+
+        let mut script = vec![];
+        generate(Shell::Bash, &config, &mut script);
+        let script = String::from_utf8(script).unwrap();
+
+        assert!(script.contains("backend"));
+        assert!(!script.contains("team/backend-api"));
+    }
+}
@@ -0,0 +1,384 @@
+use crate::exit;
+use crate::gitlab::Result;
+use directories::ProjectDirs;
+use ptree::print_config::{PrintConfig, ASCII_CHARS_TICK};
+use ptree::Style;
+use regex::Regex;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Represents glp configuration file (`~/.config/glp/config.toml`).
+/// Every field is optional - missing file or keys fall back
+/// to defaults.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Gitlab instance URL.
+    pub host: Option<String>,
+    /// Number of pipelines to show.
+    pub limit: Option<u8>,
+    /// Language of relative times and durations (like "de").
+    pub locale: Option<String>,
+    /// "compact" (like "7m 2s"), "verbose" or "seconds".
+    pub duration_format: Option<String>,
+    pub tree: TreeConfig,
+    pub variables: VariablesConfig,
+    pub excerpts: ExcerptsConfig,
+    pub connection: ConnectionConfig,
+    pub profiles: HashMap<String, Profile>,
+    pub project: HashMap<String, ProjectConfig>,
+    /// Short names of projects (alias -> ID or path).
+    pub alias: HashMap<String, String>,
+    pub budgets: BudgetsConfig,
+    pub watch: WatchConfig,
+    pub notify: NotifyConfig,
+    pub hooks: HooksConfig,
+    pub times: TimesConfig,
+}
+
+/// Represents local (per project directory) config - the `.glp`
+/// file. Either TOML with the keys below or just the project
+/// ID (legacy format).
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LocalConfig {
+    pub project: Option<String>,
+    pub host: Option<String>,
+    pub limit: Option<u8>,
+}
+
+/// Tree drawing style (`[tree]` section).
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TreeConfig {
+    /// Use ASCII-only characters (and spelled out statuses)
+    /// instead of Unicode ones. Detected from the terminal
+    /// and locale when not set.
+    pub ascii: Option<bool>,
+    /// Indentation width of each tree level.
+    pub indent: Option<usize>,
+    /// Order of jobs within a stage ("status", "duration"
+    /// or "name").
+    pub sort_jobs: Option<String>,
+    /// Style of branch lines (`[tree.branch]`).
+    pub branch: Option<Style>,
+    /// Style of item texts (`[tree.leaf]`).
+    pub leaf: Option<Style>,
+}
+
+/// Pipeline variables (`[variables]` section).
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct VariablesConfig {
+    /// Regexes - variables whose key or value match any of
+    /// them are masked on output.
+    pub secret_patterns: Option<Vec<String>>,
+}
+
+/// Failed job trace excerpts (`[excerpts]` section).
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ExcerptsConfig {
+    /// Regexes picking relevant trace lines.
+    pub patterns: Option<Vec<String>>,
+}
+
+/// Duration budgets like "10m 30s" (`[budgets]` section) -
+/// see `glp check-budgets`.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BudgetsConfig {
+    pub pipeline: Option<String>,
+    /// Job name -> budget.
+    pub jobs: HashMap<String, String>,
+}
+
+/// Watch mode (`[watch]` section).
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct WatchConfig {
+    /// Maximum polling interval like "5m" used when no
+    /// pipeline is running.
+    pub max_interval: Option<String>,
+}
+
+/// Watch mode alerts (`[notify]` section) - see
+/// `glp watch --notify`. Empty lists match everything.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct NotifyConfig {
+    /// Status transitions like "success->failed" ("*" stands
+    /// for any status).
+    pub transitions: Vec<String>,
+    /// Only pipelines of these refs.
+    pub refs: Vec<String>,
+    /// Watch these jobs instead of whole pipelines.
+    pub jobs: Vec<String>,
+}
+
+/// External commands (`[hooks]` section).
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct HooksConfig {
+    /// Command run by watch/wait modes when pipeline status
+    /// changes - `{id}`, `{ref}` and `{status}` are replaced.
+    pub on_status_change: Option<String>,
+}
+
+/// Time display (`[times]` section) - "relative", "absolute"
+/// (local time) or "both" for each field.
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TimesConfig {
+    /// Default of fields not given below.
+    pub display: Option<String>,
+    pub finished: Option<String>,
+    pub started: Option<String>,
+    pub deployed: Option<String>,
+    pub released: Option<String>,
+    pub activity: Option<String>,
+    pub next_run: Option<String>,
+}
+
+/// HTTP connection (`[connection]` section). Params take
+/// precedence.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ConnectionConfig {
+    pub proxy: Option<String>,
+    pub ca_cert: Option<String>,
+    pub client_cert: Option<String>,
+    pub client_key: Option<String>,
+    /// Maximum API page size.
+    pub per_page: Option<usize>,
+}
+
+/// Named Gitlab instance (`[profiles.<name>]` section).
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Profile {
+    /// Gitlab instance URL (e.g. "https://gitlab.example.com").
+    pub host: Option<String>,
+    /// Private token - `GLP_PRIVATE_TOKEN` is used if not set.
+    pub token: Option<String>,
+    /// Projects (IDs or paths) bound to this profile - used
+    /// without `--profile` param.
+    pub projects: Vec<String>,
+}
+
+/// Defaults applied when given project is selected
+/// (`[project."<id or path>"]` section). Params take
+/// precedence.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ProjectConfig {
+    pub limit: Option<u8>,
+    pub finished: bool,
+    pub failed_only: bool,
+    pub collapse_success: bool,
+    pub depth: Option<String>,
+    pub sort_jobs: Option<String>,
+}
+
+impl Config {
+    /// Returns path to the config file (if the home directory
+    /// can be determined).
+    pub fn path() -> Option<PathBuf> {
+        ProjectDirs::from("", "", "glp").map(|dirs| dirs.config_dir().join("config.toml"))
+    }
+
+    /// Loads config file - the given one or the default one.
+    /// Missing default file results in default config.
+    /// Unknown keys are reported as errors.
+    pub fn load(path: Option<&String>) -> Result<Config> {
+        let (path, content) = match path {
+            Some(path) => (PathBuf::from(path), fs::read_to_string(path)?),
+            None => match Self::path().and_then(|p| Some((p.clone(), fs::read_to_string(p).ok()?)))
+            {
+                Some(loaded) => loaded,
+                None => return Ok(Config::default()),
+            },
+        };
+
+        let config: Config = parse(&path, &content)?;
+        config.validate(&path)?;
+
+        Ok(config)
+    }
+
+    /// Checks values TOML types cannot - regexes so far.
+    fn validate(&self, path: &Path) -> Result<()> {
+        let patterns = [
+            ("variables.secret_patterns", &self.variables.secret_patterns),
+            ("excerpts.patterns", &self.excerpts.patterns),
+        ];
+
+        for (key, patterns) in patterns {
+            for pattern in patterns.iter().flatten() {
+                if let Err(e) = Regex::new(pattern) {
+                    return Err(exit::usage(&format!(
+                        "Invalid config file {}: `{}` has invalid regex \"{}\": {}",
+                        path.display(),
+                        key,
+                        pattern,
+                        e
+                    )));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Resolves project alias - unknown ones are returned as
+    /// they are.
+    pub fn resolve_alias(&self, project: String) -> String {
+        match self.alias.get(project.trim()) {
+            Some(resolved) => resolved.to_owned(),
+            None => project,
+        }
+    }
+
+    /// Returns defaults of given project (if any).
+    pub fn project(&self, project_id: &str) -> Option<&ProjectConfig> {
+        self.project.get(project_id.trim())
+    }
+
+    /// Picks profile by name or (if no name is given) the one
+    /// bound to the project.
+    pub fn profile(&self, name: Option<&String>, project_id: &str) -> Result<Option<&Profile>> {
+        match name {
+            Some(name) => match self.profiles.get(name) {
+                Some(profile) => Ok(Some(profile)),
+                None => Err(format!("No profile \"{}\" in config file.", name).into()),
+            },
+            None => Ok(self
+                .profiles
+                .values()
+                .find(|p| p.projects.iter().any(|id| id == project_id.trim()))),
+        }
+    }
+}
+
+impl LocalConfig {
+    pub const PATH: &'static str = ".glp";
+
+    /// Loads `.glp` file from current directory. Missing file
+    /// results in default config.
+    pub fn load() -> Result<LocalConfig> {
+        let content = match fs::read_to_string(Self::PATH) {
+            Ok(content) => content,
+            Err(_) => return Ok(LocalConfig::default()),
+        };
+
+        // Legacy format - just project ID (or path).
+        if !content.contains('=') {
+            return Ok(LocalConfig {
+                project: Some(content.trim().to_string()).filter(|p| !p.is_empty()),
+                ..LocalConfig::default()
+            });
+        }
+
+        parse(Path::new(Self::PATH), &content)
+    }
+
+    /// Writes project into `.glp` file. Other keys of TOML file
+    /// are kept, otherwise the legacy format is used.
+    pub fn save_project(project: &str) -> Result<()> {
+        let content = fs::read_to_string(Self::PATH).unwrap_or_default();
+
+        if !content.contains('=') {
+            fs::write(Self::PATH, format!("{}\n", project))?;
+            return Ok(());
+        }
+
+        let mut table: toml::value::Table = toml::from_str(&content)?;
+        table.insert("project".to_string(), project.into());
+        fs::write(Self::PATH, toml::to_string(&table)?)?;
+
+        Ok(())
+    }
+}
+
+/// Parses TOML config. Errors mention the file and suggest
+/// the closest known key in case of a typo.
+fn parse<T: DeserializeOwned>(path: &Path, content: &str) -> Result<T> {
+    toml::from_str(content).map_err(|e| {
+        let mut message = format!("Invalid config file {}: {}", path.display(), e);
+
+        if let Some(suggestion) = suggest(&e.to_string()) {
+            message.push_str(&format!(" (did you mean `{}`?)", suggestion));
+        }
+
+        message.into()
+    })
+}
+
+/// Picks known key most similar to the unknown one from
+/// "unknown field `x`, expected one of `a`, `b`" error.
+fn suggest(error: &str) -> Option<String> {
+    let unknown = Regex::new(r"unknown field `([^`]*)`, expected (.*)").unwrap();
+    let captures = unknown.captures(error)?;
+    let field = &captures[1];
+
+    Regex::new(r"`([^`]*)`")
+        .unwrap()
+        .captures_iter(&captures[2])
+        .map(|key| key[1].to_string())
+        .map(|key| (strsim::jaro_winkler(field, &key), key))
+        .filter(|(similarity, _)| *similarity > 0.8)
+        .max_by(|a, b| a.0.total_cmp(&b.0))
+        .map(|(_, key)| key)
+}
+
+impl TreeConfig {
+    /// Builds ptree print config. Starts with ptree's own
+    /// config (`PTREE_CONFIG` etc.) and overrides it with
+    /// glp settings.
+    /// The `ascii` param stands for resolved ASCII mode (see
+    /// `term::is_plain()`).
+    pub fn print_config(&self, ascii: bool) -> PrintConfig {
+        let mut print_config = PrintConfig::from_env();
+
+        if ascii {
+            print_config.characters = ASCII_CHARS_TICK.into();
+        }
+        if let Some(indent) = self.indent {
+            print_config.indent = indent;
+        }
+        if let Some(branch) = &self.branch {
+            print_config.branch = branch.clone();
+        }
+        if let Some(leaf) = &self.leaf {
+            print_config.leaf = leaf.clone();
+        }
+
+        print_config
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn invalid_patterns_are_reported_on_load() {
+        let path = std::env::temp_dir().join("glp-invalid-patterns.toml");
+
+        for (section, key) in [("variables", "secret_patterns"), ("excerpts", "patterns")] {
+            fs::write(&path, format!("[{}]\n{} = [\"error(\"]\n", section, key)).unwrap();
+
+            let error = Config::load(Some(&path.display().to_string())).unwrap_err();
+
+            assert_eq!(exit::USAGE, exit::error_code(&error));
+            assert!(error
+                .to_string()
+                .contains(&format!("`{}.{}`", section, key)));
+        }
+
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,83 @@
+use crate::gitlab::{self, Gitlab, GitlabApi};
+use crate::job::Job;
+use crate::pipeline::Pipeline;
+
+/// Approves (or rejects) blocked deployment to protected
+/// environment.
+pub async fn approve(
+    gitlab: &Gitlab,
+    id: &str,
+    approve: bool,
+    comment: Option<&String>,
+) -> gitlab::Result<()> {
+    let status = if approve { "approved" } else { "rejected" };
+    let mut params = vec![("status", status)];
+
+    if let Some(comment) = comment {
+        params.push(("comment", comment));
+    }
+
+    let approval = gitlab
+        .post(&format!("deployments/{}/approval", id), &params)
+        .await?;
+
+    match approval["status"].as_str() {
+        Some(status) => println!("Deployment {} {}.", id, status),
+        None => {
+            return Err(format!("Cannot {} deployment: {}", status, approval["message"]).into())
+        }
+    }
+
+    Ok(())
+}
+
+/// Marks manual (or scheduled) deployment jobs blocked by
+/// pending approvals or deploying to protected environments.
+/// Deployments and environments are fetched only if there is
+/// such a job.
+pub async fn mark_blocked(gitlab: &impl GitlabApi, pips: &mut [Pipeline]) -> gitlab::Result<()> {
+    let is_waiting = |job: &Job| {
+        job.environment.is_some() && matches!(job.status.as_str(), "manual" | "scheduled")
+    };
+
+    if !pips
+        .iter()
+        .flat_map(|p| p.stages.iter())
+        .flat_map(|s| s.jobs.iter())
+        .any(is_waiting)
+    {
+        return Ok(());
+    }
+
+    let deployments = gitlab.blocked_deployments().await?;
+    let awaiting = deployments
+        .members()
+        .map(|d| d["deployable"]["id"].to_string())
+        .collect::<Vec<_>>();
+    // Listing protected environments needs maintainer access.
+    let protected = match gitlab.protected_environments().await {
+        Ok(envs) => envs
+            .members()
+            .filter_map(|e| e["name"].as_str().map(|n| n.to_string()))
+            .collect::<Vec<_>>(),
+        Err(_) => vec![],
+    };
+
+    for job in pips
+        .iter_mut()
+        .flat_map(|p| p.stages.iter_mut())
+        .flat_map(|s| s.jobs.iter_mut())
+        .filter(|j| is_waiting(j))
+    {
+        if awaiting.contains(&job.id) {
+            job.blocked = Some("awaiting approval".to_string());
+        } else if job
+            .environment_name()
+            .is_some_and(|name| protected.iter().any(|p| p == name))
+        {
+            job.blocked = Some("protected environment".to_string());
+        }
+    }
+
+    Ok(())
+}
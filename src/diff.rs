@@ -0,0 +1,60 @@
+use crate::gitlab::{self, Gitlab};
+use colored::*;
+use std::collections::BTreeMap;
+
+/// Fetches test cases of pipeline test report as test name
+/// (suite, class and case name) -> status map.
+async fn fetch_tests(
+    gitlab: &Gitlab,
+    pipeline_id: &str,
+) -> gitlab::Result<BTreeMap<String, String>> {
+    let report = gitlab
+        .get(&format!("pipelines/{}/test_report", pipeline_id))
+        .await?;
+    let mut tests = BTreeMap::new();
+
+    for suite in report["test_suites"].members() {
+        for case in suite["test_cases"].members() {
+            let status = match case["status"].as_str() {
+                Some("error") => "failed",
+                Some(status) => status,
+                None => "unknown",
+            };
+
+            tests.insert(
+                format!("{} {}::{}", suite["name"], case["classname"], case["name"]),
+                status.to_string(),
+            );
+        }
+    }
+
+    Ok(tests)
+}
+
+/// Prints test cases which newly failed, newly passed or were
+/// newly skipped in pipeline `b` compared to pipeline `a`.
+pub async fn tests(gitlab: &Gitlab, a: &str, b: &str) -> gitlab::Result<()> {
+    let a_tests = fetch_tests(gitlab, a).await?;
+    let b_tests = fetch_tests(gitlab, b).await?;
+    let changed = |status: &str| {
+        b_tests
+            .iter()
+            .filter(|(name, s)| *s == status && a_tests.get(*name) != Some(s))
+            .map(|(name, _)| name.as_str())
+            .collect::<Vec<&str>>()
+    };
+
+    for (title, tests) in [
+        ("newly failed".red(), changed("failed")),
+        ("newly passed".green(), changed("success")),
+        ("newly skipped".yellow(), changed("skipped")),
+    ] {
+        println!("{} ({})", title.bold(), tests.len());
+
+        for test in tests {
+            println!("  {}", test);
+        }
+    }
+
+    Ok(())
+}
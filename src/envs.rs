@@ -0,0 +1,136 @@
+use crate::gitlab::{self, Gitlab};
+use crate::term;
+use crate::time::{ago, Field};
+use crate::watch::is_terminal;
+use crate::Label;
+use colored::*;
+use futures::future::join_all;
+use std::time::Duration;
+
+const STOP_INTERVAL: Duration = Duration::from_secs(5);
+/// Polls before giving up on the stop job being started.
+const STOP_ATTEMPTS: u32 = 3;
+
+/// Prints project environments with their latest deployment.
+/// Produces output like:
+/// `production → success 2 hours ago by @jdoe (pipeline 1234, job deploy 5678)`.
+pub async fn list(gitlab: &Gitlab) -> gitlab::Result<()> {
+    let envs = gitlab.get_all("environments").await?;
+
+    if envs.is_empty() {
+        println!("No environments.");
+    }
+
+    // List endpoint doesn't carry the last deployment.
+    let details = join_all(
+        envs.members()
+            .map(|env| async move { gitlab.get(&format!("environments/{}", env["id"])).await }),
+    )
+    .await;
+
+    for (env, detail) in envs.members().zip(details) {
+        let deployment = &detail?["last_deployment"];
+        let name = match env["state"].as_str() {
+            Some("available") => env["name"].to_string(),
+            _ => format!("{} [{}]", env["name"], env["state"])
+                .dimmed()
+                .to_string(),
+        };
+
+        if deployment.is_null() {
+            println!("{} {} -", name, term::symbol("→", "->"));
+            continue;
+        }
+
+        let status = deployment["status"].as_str().unwrap_or("");
+        let deployable = &deployment["deployable"];
+
+        println!(
+            "{} {} {} {} by @{} (pipeline {}, job {} {})",
+            name,
+            term::symbol("→", "->"),
+            Label(status.to_string()).to_string(status),
+            ago(
+                Field::Deployed,
+                deployment["created_at"].as_str().unwrap_or("")
+            ),
+            deployment["user"]["username"],
+            deployable["pipeline"]["id"],
+            deployable["name"],
+            deployable["id"]
+        );
+    }
+
+    Ok(())
+}
+
+/// Stops environment with given name. Its on_stop job (if
+/// any) is followed until it finishes.
+pub async fn stop(gitlab: &Gitlab, name: &str) -> gitlab::Result<()> {
+    let envs = gitlab
+        .get(&format!("environments?name={}", gitlab::encode(name)))
+        .await?;
+    let id = match envs[0]["id"].as_usize() {
+        Some(id) => id,
+        None => return Err(format!("Environment \"{}\" not found.", name).into()),
+    };
+
+    let detail = gitlab.get(&format!("environments/{}", id)).await?;
+    let env = gitlab
+        .post(&format!("environments/{}/stop", id), &[])
+        .await?;
+
+    match env["state"].as_str() {
+        Some(state) => println!("Environment \"{}\" is {}.", name, state),
+        None => return Err(format!("Cannot stop environment: {}", env["message"]).into()),
+    }
+
+    // The on_stop job lives in the pipeline of the last
+    // deployment.
+    let pipeline_id = &detail["last_deployment"]["deployable"]["pipeline"]["id"];
+
+    if pipeline_id.is_null() {
+        return Ok(());
+    }
+
+    let jobs = gitlab
+        .get_all(&format!("pipelines/{}/jobs", pipeline_id))
+        .await?;
+    let stop_job = jobs
+        .members()
+        .find(|job| job["environment"]["name"] == name && job["environment"]["action"] == "stop");
+
+    match stop_job {
+        Some(job) => follow_job(gitlab, &job["id"].to_string()).await,
+        None => Ok(()),
+    }
+}
+
+/// Waits for stop job to finish and reports its status.
+async fn follow_job(gitlab: &Gitlab, id: &str) -> gitlab::Result<()> {
+    let mut attempts = 0;
+
+    loop {
+        let job = gitlab.get(&format!("jobs/{}", id)).await?;
+        let status = job["status"].as_str().unwrap_or("");
+
+        // Manual job may take a while to get started.
+        if is_terminal(status) || ("manual" == status && attempts >= STOP_ATTEMPTS) {
+            println!(
+                "Stop job {} {} {} {}",
+                job["name"],
+                id,
+                term::symbol("→", "->"),
+                Label(status.to_string()).to_string(status)
+            );
+
+            return match status {
+                "success" => Ok(()),
+                _ => Err(format!("Stop job {} did not succeed.", id).into()),
+            };
+        }
+
+        attempts += 1;
+        tokio::time::sleep(STOP_INTERVAL).await;
+    }
+}
@@ -0,0 +1,58 @@
+use crate::logs::clean_trace;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+const EXCERPT_LINES: usize = 5;
+pub const DEFAULT_PATTERNS: [&str; 4] = [
+    r"(?i)\berror\b",
+    r"FAILED",
+    r"panicked at",
+    r"^\s*\d+: .+::",
+];
+
+/// Represents single relevant line of a failed job trace
+/// rendered under the job.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TraceLine {
+    pub text: String,
+    /// Matches an excerpt pattern (otherwise it's a line of
+    /// the trace tail).
+    pub matched: bool,
+}
+
+/// Picks the most relevant lines of a failed job trace - the
+/// last lines matching any of given patterns. Falls back to
+/// the trace tail if nothing matches.
+pub fn extract(trace: &str, patterns: &[Regex]) -> Vec<TraceLine> {
+    let trace = clean_trace(trace);
+    let lines = trace
+        .lines()
+        .map(|l| l.trim_end())
+        .filter(|l| !l.is_empty())
+        .collect::<Vec<&str>>();
+
+    let matching = lines
+        .iter()
+        .filter(|l| patterns.iter().any(|p| p.is_match(l)))
+        .collect::<Vec<_>>();
+
+    if matching.is_empty() {
+        return lines
+            .iter()
+            .skip(lines.len().saturating_sub(EXCERPT_LINES))
+            .map(|l| TraceLine {
+                text: l.to_string(),
+                matched: false,
+            })
+            .collect();
+    }
+
+    matching
+        .iter()
+        .skip(matching.len().saturating_sub(EXCERPT_LINES))
+        .map(|l| TraceLine {
+            text: l.to_string(),
+            matched: true,
+        })
+        .collect()
+}
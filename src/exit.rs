@@ -0,0 +1,85 @@
+use crate::gitlab::Error;
+use std::fmt;
+
+// Exit codes - stable, see README.
+pub const SUCCESS: i32 = 0;
+/// Pipeline (or job) failed.
+pub const FAILED: i32 = 1;
+/// Invalid params or settings (also used by argument parser).
+pub const USAGE: i32 = 2;
+/// Pipeline is not finished yet (or blocked by manual or
+/// scheduled jobs), was canceled or skipped.
+pub const UNFINISHED: i32 = 3;
+/// Pipeline didn't finish in time (`--timeout`).
+pub const TIMEOUT: i32 = 4;
+/// Missing, invalid or insufficient private token.
+pub const AUTH: i32 = 5;
+/// Gitlab cannot be reached.
+pub const NETWORK: i32 = 6;
+/// Any other error.
+pub const ERROR: i32 = 7;
+
+/// Error carrying its own exit code.
+#[derive(Debug)]
+pub struct Failure {
+    pub code: i32,
+    message: String,
+}
+
+impl fmt::Display for Failure {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl std::error::Error for Failure {}
+
+fn failure(code: i32, message: &str) -> Error {
+    Box::new(Failure {
+        code,
+        message: message.to_string(),
+    })
+}
+
+/// Creates usage error (exits with `USAGE` code).
+pub fn usage(message: &str) -> Error {
+    failure(USAGE, message)
+}
+
+/// Creates timeout error (exits with `TIMEOUT` code).
+pub fn timeout(message: &str) -> Error {
+    failure(TIMEOUT, message)
+}
+
+/// Creates authentication error (exits with `AUTH` code).
+pub fn auth(message: &str) -> Error {
+    failure(AUTH, message)
+}
+
+/// Maps pipeline (or job) status to exit code.
+pub fn status_code(status: &str) -> i32 {
+    match status {
+        "success" => SUCCESS,
+        "failed" => FAILED,
+        // Also "manual" and "scheduled" - blocked pipelines.
+        _ => UNFINISHED,
+    }
+}
+
+/// Picks exit code for given error.
+pub fn error_code(error: &Error) -> i32 {
+    if let Some(failure) = error.downcast_ref::<Failure>() {
+        return failure.code;
+    }
+
+    match error.downcast_ref::<reqwest::Error>() {
+        Some(e)
+            if e.status()
+                .is_some_and(|s| s.as_u16() == 401 || s.as_u16() == 403) =>
+        {
+            AUTH
+        }
+        Some(e) if e.is_connect() || e.is_timeout() || e.is_request() => NETWORK,
+        _ => ERROR,
+    }
+}
@@ -0,0 +1,516 @@
+use colored::*;
+use json::JsonValue;
+use reqwest::{Certificate, Identity, Method, RequestBuilder, StatusCode};
+use std::fs;
+use std::future::Future;
+
+pub const GITLAB_URL: &str = "https://gitlab.com";
+const MAX_PER_PAGE: usize = 100;
+
+pub type Error = Box<dyn std::error::Error + Send + Sync>;
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// HTTP connection settings.
+#[derive(Debug, Default)]
+pub struct Connection {
+    /// Gitlab instance URL - gitlab.com when not set.
+    pub host: Option<String>,
+    /// Proxy for all requests (e.g. `socks5://localhost:1080`).
+    /// `HTTPS_PROXY`/`HTTP_PROXY` environment variables are
+    /// honored when not set.
+    pub proxy: Option<String>,
+    /// Path to PEM file with additional trusted CA certificate(s).
+    pub ca_cert: Option<String>,
+    /// Paths to PEM files with client certificate and its
+    /// (PKCS#8) private key for mutual TLS.
+    pub client_cert: Option<String>,
+    pub client_key: Option<String>,
+    /// Skip TLS certificate verification.
+    pub insecure: bool,
+    /// Maximum API page size - for instances with lower limit
+    /// than the default 100.
+    pub per_page: Option<usize>,
+}
+
+impl Connection {
+    /// Builds HTTP client according to the settings.
+    fn client(&self) -> Result<reqwest::Client> {
+        let mut builder = reqwest::Client::builder();
+
+        if let Some(proxy) = &self.proxy {
+            builder = builder.proxy(reqwest::Proxy::all(proxy)?);
+        }
+
+        if let Some(path) = &self.ca_cert {
+            let pem = fs::read(path)?;
+            builder = builder.add_root_certificate(Certificate::from_pem(&pem)?);
+        }
+
+        match (&self.client_cert, &self.client_key) {
+            (Some(cert), Some(key)) => {
+                let identity = Identity::from_pkcs8_pem(&fs::read(cert)?, &fs::read(key)?)?;
+                builder = builder.identity(identity);
+            }
+            (None, None) => {}
+            _ => return Err("Both client certificate and key have to be given.".into()),
+        }
+
+        if self.insecure {
+            eprintln!(
+                "{}",
+                "Warning: TLS certificate verification is disabled (--insecure).".yellow()
+            );
+            builder = builder.danger_accept_invalid_certs(true);
+        }
+
+        Ok(builder.build()?)
+    }
+}
+
+/// Gitlab REST API client scoped to a single project.
+/// Cheap to clone - the underlying HTTP client is shared.
+#[derive(Debug, Clone)]
+pub struct Gitlab {
+    client: reqwest::Client,
+    url: String,
+    private_token: String,
+    project_id: String,
+    per_page: usize,
+}
+
+impl Gitlab {
+    /// Project can be given as numeric ID or as a full path
+    /// ("group/project").
+    pub fn new(private_token: &str, project_id: &str, connection: &Connection) -> Result<Gitlab> {
+        Ok(Gitlab {
+            client: connection.client()?,
+            url: connection
+                .host
+                .as_deref()
+                .unwrap_or(GITLAB_URL)
+                .trim_end_matches('/')
+                .to_string(),
+            private_token: private_token.to_string(),
+            project_id: encode(project_id.trim()),
+            per_page: connection.per_page.unwrap_or(MAX_PER_PAGE).max(1),
+        })
+    }
+
+    /// Switches client to another project.
+    pub fn set_project_id(&mut self, project_id: &str) {
+        self.project_id = encode(project_id.trim());
+    }
+
+    /// Gitlab instance URL (without trailing slash).
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// Builds authenticated request to project scoped endpoint,
+    /// e.g. `pipelines/123` stands for `/projects/:id/pipelines/123`.
+    /// Empty path stands for the project itself.
+    fn request(&self, method: Method, path: &str) -> RequestBuilder {
+        let mut project_path = format!("projects/{}", self.project_id);
+
+        if !path.is_empty() {
+            project_path.push('/');
+            project_path.push_str(path);
+        }
+
+        self.api_request(method, &project_path)
+    }
+
+    /// Builds authenticated request to any API endpoint,
+    /// e.g. `projects` stands for `/projects`.
+    fn api_request(&self, method: Method, path: &str) -> RequestBuilder {
+        self.client
+            .request(method, format!("{}/api/v4/{}", self.url, path))
+            .header("PRIVATE-TOKEN", &self.private_token)
+    }
+
+    /// Sends request and parses JSON response. Rejected
+    /// token is turned into an error, other error responses
+    /// are returned as they are.
+    async fn send(request: RequestBuilder) -> Result<JsonValue> {
+        let response = request.send().await?;
+
+        if response.status() == StatusCode::UNAUTHORIZED {
+            return Err(response.error_for_status().unwrap_err().into());
+        }
+
+        Ok(json::parse(&response.text().await?)?)
+    }
+
+    /// Fetches the project itself.
+    pub async fn project(&self) -> Result<JsonValue> {
+        Self::send(self.request(Method::GET, "")).await
+    }
+
+    /// Fetches endpoint which is not project scoped (single
+    /// page of the maximum size).
+    pub async fn get_global(&self, path: &str) -> Result<JsonValue> {
+        let separator = if path.contains('?') { '&' } else { '?' };
+
+        Self::send(self.api_request(
+            Method::GET,
+            &format!("{}{}per_page={}", path, separator, self.per_page),
+        ))
+        .await
+    }
+
+    /// Fetches project scoped endpoint.
+    pub async fn get(&self, path: &str) -> Result<JsonValue> {
+        Self::send(self.request(Method::GET, path)).await
+    }
+
+    /// Fetches up to `count` items of project scoped listing
+    /// page by page. Page size is picked so the fewest requests
+    /// are made.
+    pub async fn get_paged(&self, path: &str, count: usize) -> Result<JsonValue> {
+        let separator = if path.contains('?') { '&' } else { '?' };
+        let per_page = count.clamp(1, self.per_page);
+        let mut items = JsonValue::new_array();
+        let mut page = 1;
+
+        while items.len() < count {
+            let response = self
+                .request(
+                    Method::GET,
+                    &format!("{}{}per_page={}&page={}", path, separator, per_page, page),
+                )
+                .send()
+                .await?
+                .error_for_status()?;
+            let last = response
+                .headers()
+                .get("x-next-page")
+                .and_then(|next| next.to_str().ok())
+                .is_none_or(|next| next.is_empty());
+            let page_items = json::parse(&response.text().await?)?;
+
+            for item in page_items.members().take(count - items.len()) {
+                items.push(item.clone())?;
+            }
+
+            if last || page_items.is_empty() {
+                break;
+            }
+
+            page += 1;
+        }
+
+        Ok(items)
+    }
+
+    /// Fetches all items of project scoped listing.
+    pub async fn get_all(&self, path: &str) -> Result<JsonValue> {
+        self.get_paged(path, usize::MAX).await
+    }
+
+    /// Fetches up to `count` items of project scoped listing
+    /// using keyset pagination (follows "next" links). Unlike
+    /// offset pages keyset ones are not capped nor throttled on
+    /// deep history. The listing has to be ordered by ID.
+    pub async fn get_keyset(&self, path: &str, count: usize) -> Result<JsonValue> {
+        let separator = if path.contains('?') { '&' } else { '?' };
+        let mut request = self.request(
+            Method::GET,
+            &format!(
+                "{}{}pagination=keyset&per_page={}",
+                path,
+                separator,
+                count.clamp(1, self.per_page)
+            ),
+        );
+        let mut items = JsonValue::new_array();
+
+        loop {
+            let response = request.send().await?.error_for_status()?;
+            let next = response
+                .headers()
+                .get(reqwest::header::LINK)
+                .and_then(|link| link.to_str().ok())
+                .and_then(next_link);
+            let page = json::parse(&response.text().await?)?;
+
+            for item in page.members().take(count - items.len()) {
+                items.push(item.clone())?;
+            }
+
+            match next {
+                Some(url) if items.len() < count && !page.is_empty() => {
+                    request = self
+                        .client
+                        .get(url)
+                        .header("PRIVATE-TOKEN", &self.private_token);
+                }
+                _ => return Ok(items),
+            }
+        }
+    }
+
+    /// Fetches project scoped endpoint returning plain text
+    /// (e.g. job trace).
+    pub async fn get_text(&self, path: &str) -> Result<String> {
+        let response = self
+            .request(Method::GET, path)
+            .send()
+            .await?
+            .error_for_status()?;
+
+        Ok(response.text().await?)
+    }
+
+    /// Posts JSON body to project scoped endpoint.
+    pub async fn post_json(&self, path: &str, body: &JsonValue) -> Result<JsonValue> {
+        Self::send(
+            self.request(Method::POST, path)
+                .header("Content-Type", "application/json")
+                .body(body.dump()),
+        )
+        .await
+    }
+
+    /// Posts to project scoped endpoint with given form
+    /// parameters.
+    pub async fn post(&self, path: &str, params: &[(&str, &str)]) -> Result<JsonValue> {
+        Self::send(self.request(Method::POST, path).form(params)).await
+    }
+
+    /// Runs GraphQL query and returns its "data" part.
+    /// Errors reported by the API are turned into an error.
+    pub async fn graphql(&self, query: &str, variables: JsonValue) -> Result<JsonValue> {
+        let body = json::object! { query: query, variables: variables };
+        let mut response = Self::send(
+            self.client
+                .post(format!("{}/api/graphql", self.url))
+                .header("Authorization", format!("Bearer {}", self.private_token))
+                .header("Content-Type", "application/json")
+                .body(body.dump()),
+        )
+        .await?;
+
+        if !response["errors"].is_empty() {
+            return Err(
+                format!("GraphQL query failed: {}", response["errors"][0]["message"]).into(),
+            );
+        }
+
+        Ok(response["data"].take())
+    }
+}
+
+/// Project scoped Gitlab API calls pipeline trees are built
+/// from. Implemented by `Gitlab` (REST API at given base URL)
+/// - other implementations can serve fake data.
+pub trait GitlabApi {
+    /// Fetches up to `count` pipelines matching given query
+    /// (like "status=failed&order_by=id&sort=desc").
+    fn list_pipelines(
+        &self,
+        query: &str,
+        count: usize,
+    ) -> impl Future<Output = Result<JsonValue>> + Send;
+    /// Fetches all jobs of given pipeline.
+    fn list_jobs(&self, pipeline_id: &str) -> impl Future<Output = Result<JsonValue>> + Send;
+    /// Fetches all bridges (trigger jobs) of given pipeline.
+    fn list_bridges(&self, pipeline_id: &str) -> impl Future<Output = Result<JsonValue>> + Send;
+    fn pipeline_details(&self, pipeline_id: &str)
+        -> impl Future<Output = Result<JsonValue>> + Send;
+    fn pipeline_variables(
+        &self,
+        pipeline_id: &str,
+    ) -> impl Future<Output = Result<JsonValue>> + Send;
+    fn merge_request(&self, iid: &str) -> impl Future<Output = Result<JsonValue>> + Send;
+    fn job_trace(&self, job_id: &str) -> impl Future<Output = Result<String>> + Send;
+    /// Gitlab instance URL (without trailing slash).
+    fn url(&self) -> &str;
+    fn project(&self) -> impl Future<Output = Result<JsonValue>> + Send;
+    /// Validates CI config of given commit (merged YAML
+    /// without jobs).
+    fn ci_lint(&self, sha: &str) -> impl Future<Output = Result<JsonValue>> + Send;
+    /// Runs GraphQL query and returns its "data" part.
+    fn graphql(
+        &self,
+        query: &str,
+        variables: JsonValue,
+    ) -> impl Future<Output = Result<JsonValue>> + Send;
+    /// Fetches deployments waiting for approval.
+    fn blocked_deployments(&self) -> impl Future<Output = Result<JsonValue>> + Send;
+    fn protected_environments(&self) -> impl Future<Output = Result<JsonValue>> + Send;
+}
+
+impl GitlabApi for Gitlab {
+    async fn list_pipelines(&self, query: &str, count: usize) -> Result<JsonValue> {
+        let path = format!("pipelines?{}", query);
+
+        // Keyset pagination is available for ID ordering only.
+        match query.contains("order_by=id") {
+            true => self.get_keyset(&path, count).await,
+            false => self.get_paged(&path, count).await,
+        }
+    }
+
+    async fn list_jobs(&self, pipeline_id: &str) -> Result<JsonValue> {
+        self.get_all(&format!("pipelines/{}/jobs", pipeline_id))
+            .await
+    }
+
+    async fn list_bridges(&self, pipeline_id: &str) -> Result<JsonValue> {
+        self.get_all(&format!("pipelines/{}/bridges", pipeline_id))
+            .await
+    }
+
+    async fn pipeline_details(&self, pipeline_id: &str) -> Result<JsonValue> {
+        self.get(&format!("pipelines/{}", pipeline_id)).await
+    }
+
+    async fn pipeline_variables(&self, pipeline_id: &str) -> Result<JsonValue> {
+        self.get(&format!("pipelines/{}/variables", pipeline_id))
+            .await
+    }
+
+    async fn merge_request(&self, iid: &str) -> Result<JsonValue> {
+        self.get(&format!("merge_requests/{}", iid)).await
+    }
+
+    async fn job_trace(&self, job_id: &str) -> Result<String> {
+        self.get_text(&format!("jobs/{}/trace", job_id)).await
+    }
+
+    fn url(&self) -> &str {
+        Gitlab::url(self)
+    }
+
+    async fn project(&self) -> Result<JsonValue> {
+        Gitlab::project(self).await
+    }
+
+    async fn ci_lint(&self, sha: &str) -> Result<JsonValue> {
+        self.get(&format!("ci/lint?sha={}&include_jobs=false", sha))
+            .await
+    }
+
+    async fn graphql(&self, query: &str, variables: JsonValue) -> Result<JsonValue> {
+        Gitlab::graphql(self, query, variables).await
+    }
+
+    async fn blocked_deployments(&self) -> Result<JsonValue> {
+        self.get_all("deployments?status=blocked").await
+    }
+
+    async fn protected_environments(&self) -> Result<JsonValue> {
+        self.get_all("protected_environments").await
+    }
+}
+
+/// Picks URL of the next page from `Link` response header.
+fn next_link(header: &str) -> Option<String> {
+    header
+        .split(',')
+        .find(|link| link.contains("rel=\"next\""))
+        .and_then(|link| {
+            let start = link.find('<')?;
+            let end = link.find('>')?;
+
+            Some(link[start + 1..end].to_string())
+        })
+}
+
+/// Encodes given string to be used as a single URL path
+/// segment or query value.
+pub fn encode(value: &str) -> String {
+    url::form_urlencoded::byte_serialize(value.as_bytes()).collect()
+}
+
+/// In-memory `GitlabApi` serving canned responses - jobs and
+/// bridges are keyed by pipeline ID, missing ones are empty.
+/// GraphQL API is unavailable unless its data are given.
+#[cfg(test)]
+pub mod fake {
+    use super::{GitlabApi, Result};
+    use json::JsonValue;
+    use std::collections::HashMap;
+
+    #[derive(Debug, Default, Clone)]
+    pub struct FakeGitlab {
+        pub pipelines: Vec<JsonValue>,
+        pub jobs: HashMap<String, JsonValue>,
+        pub bridges: HashMap<String, JsonValue>,
+        pub graphql: Option<JsonValue>,
+    }
+
+    impl GitlabApi for FakeGitlab {
+        async fn list_pipelines(&self, _query: &str, count: usize) -> Result<JsonValue> {
+            Ok(self
+                .pipelines
+                .iter()
+                .take(count)
+                .cloned()
+                .collect::<Vec<_>>()
+                .into())
+        }
+
+        async fn list_jobs(&self, pipeline_id: &str) -> Result<JsonValue> {
+            Ok(self
+                .jobs
+                .get(pipeline_id)
+                .cloned()
+                .unwrap_or(JsonValue::new_array()))
+        }
+
+        async fn list_bridges(&self, pipeline_id: &str) -> Result<JsonValue> {
+            Ok(self
+                .bridges
+                .get(pipeline_id)
+                .cloned()
+                .unwrap_or(JsonValue::new_array()))
+        }
+
+        async fn pipeline_details(&self, pipeline_id: &str) -> Result<JsonValue> {
+            self.pipelines
+                .iter()
+                .find(|p| p["id"].as_u64() == pipeline_id.parse().ok())
+                .cloned()
+                .ok_or_else(|| "404 Not found".into())
+        }
+
+        async fn pipeline_variables(&self, _pipeline_id: &str) -> Result<JsonValue> {
+            Ok(JsonValue::new_array())
+        }
+
+        async fn merge_request(&self, _iid: &str) -> Result<JsonValue> {
+            Err("404 Not found".into())
+        }
+
+        async fn job_trace(&self, _job_id: &str) -> Result<String> {
+            Ok(String::new())
+        }
+
+        fn url(&self) -> &str {
+            "https://gitlab.example.com"
+        }
+
+        async fn project(&self) -> Result<JsonValue> {
+            Ok(json::object! { path_with_namespace: "group/project" })
+        }
+
+        async fn ci_lint(&self, _sha: &str) -> Result<JsonValue> {
+            Err("404 Not found".into())
+        }
+
+        async fn graphql(&self, _query: &str, _variables: JsonValue) -> Result<JsonValue> {
+            self.graphql
+                .clone()
+                .ok_or_else(|| "GraphQL is not available".into())
+        }
+
+        async fn blocked_deployments(&self) -> Result<JsonValue> {
+            Ok(JsonValue::new_array())
+        }
+
+        async fn protected_environments(&self) -> Result<JsonValue> {
+            Ok(JsonValue::new_array())
+        }
+    }
+}
@@ -0,0 +1,77 @@
+use crate::config::HooksConfig;
+use crate::pipeline::Pipeline;
+use std::collections::HashMap;
+use std::process::{Child, Command};
+
+/// Runs `hooks.on_status_change` command whenever a pipeline
+/// status changes between refreshes.
+pub struct StatusHook<'a> {
+    command: Option<&'a str>,
+    /// Last seen status by pipeline ID.
+    statuses: HashMap<String, String>,
+    /// Commands still running - reaped on refreshes so they
+    /// don't linger as zombies.
+    children: Vec<Child>,
+}
+
+impl<'a> StatusHook<'a> {
+    pub fn new(config: &'a HooksConfig) -> StatusHook<'a> {
+        StatusHook {
+            command: config.on_status_change.as_deref(),
+            statuses: HashMap::new(),
+            children: vec![],
+        }
+    }
+
+    /// Runs the command for each pipeline whose status differs
+    /// from the previously seen one. Pipelines seen for the
+    /// first time don't count.
+    pub fn update(&mut self, pips: &[Pipeline]) {
+        let command = match self.command {
+            Some(command) => command,
+            None => return,
+        };
+
+        // Keep only commands which haven't exited yet.
+        self.children
+            .retain_mut(|child| matches!(child.try_wait(), Ok(None)));
+
+        for pip in pips {
+            let previous = self.statuses.insert(pip.id.0.clone(), pip.status.clone());
+
+            if previous.is_some_and(|p| p != pip.status) {
+                self.children.extend(run(command, pip));
+            }
+        }
+    }
+}
+
+/// Runs the command (through shell) with `{id}`, `{ref}` and
+/// `{status}` placeholders filled in. Failures are reported
+/// but don't stop watching.
+fn run(command: &str, pip: &Pipeline) -> Option<Child> {
+    let command = command
+        .replace("{id}", &quote(&pip.id.0))
+        .replace("{ref}", &quote(&pip.git_ref))
+        .replace("{status}", &quote(&pip.status));
+    let result = match cfg!(windows) {
+        true => Command::new("cmd").args(["/C", &command]).spawn(),
+        false => Command::new("sh").args(["-c", &command]).spawn(),
+    };
+
+    match result {
+        Ok(child) => Some(child),
+        Err(e) => {
+            eprintln!("Cannot run on_status_change hook: {}", e);
+            None
+        }
+    }
+}
+
+/// Quotes value for shell (refs may contain anything).
+fn quote(value: &str) -> String {
+    match cfg!(windows) {
+        true => format!("\"{}\"", value.replace('"', "")),
+        false => format!("'{}'", value.replace('\'', r"'\''")),
+    }
+}
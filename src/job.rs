@@ -1,12 +1,12 @@
+use crate::excerpt::TraceLine;
+use crate::time;
 use crate::Label;
-use humantime::format_duration;
-use ptree;
-/// Represents Gitlab pipeline job.
-use std::borrow::Cow;
-use std::io;
+use json::JsonValue;
+use serde::{Deserialize, Serialize};
 use std::time::Duration;
 
-#[derive(Debug, Clone)]
+/// Represents Gitlab pipeline job.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Job {
     pub id: String,
     pub name: Label,
@@ -14,29 +14,195 @@ pub struct Job {
     pub status: String,
     pub stage: String,
     pub started_at: Option<String>,
+    /// Serialized in seconds.
+    #[serde(with = "duration_secs")]
     pub duration: Option<Duration>,
+    /// Test coverage percentage reported by the job.
+    pub coverage: Option<f64>,
+    pub excerpt: Vec<TraceLine>,
+    /// Downstream pipeline of bridge (trigger) job, like
+    /// "group/project 1234".
+    pub downstream: Option<String>,
+    /// Environment the job deploys to, with action unless it
+    /// is the default "start" (e.g. "review/feature [stop]").
+    pub environment: Option<String>,
+    /// Size of downloadable artifacts archive (if any).
+    pub artifacts_size: Option<u64>,
+    /// Reason the job cannot run yet (e.g. "awaiting
+    /// approval").
+    pub blocked: Option<String>,
+    /// Names of jobs this one needs (GraphQL API only - REST
+    /// API doesn't list them).
+    pub needs: Vec<String>,
 }
 
-impl ptree::TreeItem for Job {
-    type Child = Self;
+impl Job {
+    /// Creates job from Gitlab API job JSON.
+    pub fn from_json(job: &JsonValue) -> Job {
+        Job {
+            id: job["id"].as_usize().unwrap().to_string(),
+            name: Label(job["name"].as_str().unwrap().to_string()),
+            status: job["status"].as_str().unwrap().to_string(),
+            web_url: job["web_url"].as_str().unwrap().to_string(),
+            stage: job["stage"].as_str().unwrap().to_string(),
+            started_at: match job["started_at"].is_null() {
+                true => None,
+                false => Some(job["started_at"].as_str().unwrap().to_string()),
+            },
+            duration: match job["duration"].is_null() {
+                true => None,
+                false => Some(Duration::from_secs_f64(job["duration"].as_f64().unwrap())),
+            },
+            coverage: job["coverage"].as_f64(),
+            excerpt: vec![],
+            downstream: None,
+            environment: job["environment"]["name"].as_str().map(|name| {
+                match job["environment"]["action"].as_str() {
+                    None | Some("start") => name.to_string(),
+                    Some(action) => format!("{} [{}]", name, action),
+                }
+            }),
+            artifacts_size: job["artifacts_file"]["size"].as_u64(),
+            blocked: None,
+            needs: vec![],
+        }
+    }
+
+    /// Creates job from Gitlab API bridge (trigger job) JSON.
+    pub fn from_bridge(bridge: &JsonValue) -> Job {
+        let mut job = Job::from_json(bridge);
+        let downstream = &bridge["downstream_pipeline"];
+
+        if !downstream.is_null() {
+            // Only project ID is given - take the path from URL
+            // like "https://gitlab.com/group/project/-/pipelines/1234".
+            let url = downstream["web_url"].as_str().unwrap_or("");
+            let project = url
+                .split("/-/")
+                .next()
+                .and_then(|u| u.splitn(4, '/').nth(3))
+                .unwrap_or("");
+            let status = downstream["status"].as_str().unwrap_or("");
+
+            job.downstream = Some(downstream_str(
+                project,
+                &downstream["id"].to_string(),
+                status,
+            ));
+        }
+
+        job
+    }
+
+    /// Creates job from Gitlab GraphQL API job node.
+    /// Job web path is resolved against given Gitlab URL.
+    pub fn from_graphql(job: &JsonValue, stage: &str, gitlab_url: &str) -> Job {
+        let downstream = &job["downstreamPipeline"];
+
+        Job {
+            id: global_id(&job["id"]),
+            name: Label(job["name"].as_str().unwrap().to_string()),
+            status: job["status"].as_str().unwrap().to_lowercase(),
+            web_url: job["webPath"]
+                .as_str()
+                .map(|path| format!("{}{}", gitlab_url, path))
+                .unwrap_or_default(),
+            stage: stage.to_string(),
+            started_at: job["startedAt"].as_str().map(|s| s.to_string()),
+            duration: job["duration"].as_u64().map(Duration::from_secs),
+            coverage: job["coverage"].as_f64(),
+            excerpt: vec![],
+            downstream: match downstream.is_null() {
+                true => None,
+                false => Some(downstream_str(
+                    downstream["project"]["fullPath"].as_str().unwrap_or(""),
+                    &global_id(&downstream["id"]),
+                    &downstream["status"].as_str().unwrap_or("").to_lowercase(),
+                )),
+            },
+            environment: job["environment"]["name"].as_str().map(|n| n.to_string()),
+            artifacts_size: job["artifacts"]["nodes"]
+                .members()
+                .find(|a| a["fileType"] == "ARCHIVE")
+                // BigInt - sent as string.
+                .and_then(|a| a["size"].as_str()?.parse().ok()),
+            blocked: None,
+            needs: job["needs"]["nodes"]
+                .members()
+                .filter_map(|n| n["name"].as_str().map(|n| n.to_string()))
+                .collect(),
+        }
+    }
+}
 
-    fn write_self<W: io::Write>(&self, f: &mut W, _style: &ptree::Style) -> io::Result<()> {
-        let duration_str = match self.duration {
+/// Strips global ID like "gid://gitlab/Ci::Build/123" down to
+/// the plain ID.
+fn global_id(id: &JsonValue) -> String {
+    id.as_str()
+        .and_then(|id| id.rsplit('/').next())
+        .unwrap_or_default()
+        .to_string()
+}
+
+/// Formats downstream pipeline like "group/project 1234"
+/// (ID colored by its status).
+fn downstream_str(project: &str, id: &str, status: &str) -> String {
+    format!("{} {}", project, Label(id.to_string()).to_string(status))
+}
+
+impl Job {
+    /// Formats duration like "7m 2s" - "-" if there is none.
+    pub fn duration_str(&self) -> String {
+        match self.duration {
             // Keep duration seconds and forget the subtle resolution.
-            // Use "-" as fallback in case of no duration at all.
-            Some(duration) => format_duration(Duration::from_secs(duration.as_secs())).to_string(),
+            Some(duration) => time::duration(Duration::from_secs(duration.as_secs())),
             _ => "-".to_string(),
-        };
+        }
+    }
+
+    /// Name of the environment the job deploys to (without
+    /// action).
+    pub fn environment_name(&self) -> Option<&str> {
+        self.environment
+            .as_deref()
+            .and_then(|e| e.split(" [").next())
+    }
+
+    /// Converts job to JSON for machine readable outputs.
+    pub fn to_json(&self) -> JsonValue {
+        json::object! {
+            id: self.id.parse::<u64>().ok(),
+            name: self.name.0.clone(),
+            status: self.status.clone(),
+            stage: self.stage.clone(),
+            started_at: self.started_at.as_deref().map(time::timestamp),
+            duration: self.duration.map(|d| d.as_secs()),
+            coverage: self.coverage,
+            environment: self.environment.clone(),
+            artifacts_size: self.artifacts_size,
+            blocked: self.blocked.clone(),
+            needs: self.needs.clone(),
+            web_url: self.web_url.clone(),
+        }
+    }
+}
+
+/// (De)serializes optional duration as seconds (with
+/// fraction) instead of serde's `{secs, nanos}`.
+mod duration_secs {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+    use std::time::Duration;
 
-        write!(
-            f,
-            "{} ({})",
-            &self.name.to_string(&self.status),
-            duration_str
-        )
+    pub fn serialize<S: Serializer>(
+        duration: &Option<Duration>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        duration.map(|d| d.as_secs_f64()).serialize(serializer)
     }
 
-    fn children(&self) -> Cow<[Self::Child]> {
-        Cow::from(vec![])
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<Duration>, D::Error> {
+        Ok(Option::<f64>::deserialize(deserializer)?.map(Duration::from_secs_f64))
     }
 }
@@ -0,0 +1,135 @@
+use crate::gitlab::{self, Gitlab};
+use crate::output;
+use crate::pipeline::Pipeline;
+use crate::prompt;
+use crate::term;
+use crate::time::{self, ago, Field};
+use crate::Label;
+use std::time::Duration;
+
+/// Prints recent jobs across all pipelines as a table
+/// (newest first), optionally limited to given statuses.
+pub async fn list(gitlab: &Gitlab, statuses: &[String], limit: u8) -> gitlab::Result<()> {
+    let mut path = "jobs?".to_string();
+
+    for status in statuses {
+        path.push_str(&format!("scope[]={}&", gitlab::encode(status)));
+    }
+
+    let jobs = gitlab.get_paged(&path, limit as usize).await?;
+
+    if jobs.is_empty() {
+        println!("No jobs.");
+        return Ok(());
+    }
+
+    let mut rows = vec![[
+        "ID".to_string(),
+        "STATUS".to_string(),
+        "STAGE".to_string(),
+        "NAME".to_string(),
+        "REF".to_string(),
+        "PIPELINE".to_string(),
+        "DURATION".to_string(),
+        "STARTED".to_string(),
+    ]];
+
+    for job in jobs.members() {
+        rows.push([
+            job["id"].to_string(),
+            job["status"].to_string(),
+            job["stage"].to_string(),
+            job["name"].to_string(),
+            job["ref"].to_string(),
+            job["pipeline"]["id"].to_string(),
+            match job["duration"].as_f64() {
+                Some(duration) => time::duration(Duration::from_secs(duration as u64)),
+                None => "-".to_string(),
+            },
+            ago(Field::Started, job["started_at"].as_str().unwrap_or("")),
+        ]);
+    }
+
+    print!("{}", output::table(&rows, 1));
+
+    Ok(())
+}
+
+/// Erases job log and artifacts (after confirmation unless
+/// `yes` is set).
+pub async fn erase(gitlab: &Gitlab, id: u64, yes: bool) -> gitlab::Result<()> {
+    let job = gitlab.get(&format!("jobs/{}", id)).await?;
+
+    if job["id"].is_null() {
+        return Err(format!("Job {} not found.", id).into());
+    }
+
+    if !yes
+        && !prompt::confirm(&format!(
+            "Erase log and artifacts of job {} ({})?",
+            id, job["name"]
+        ))
+    {
+        return Ok(());
+    }
+
+    let job = gitlab.post(&format!("jobs/{}/erase", id), &[]).await?;
+
+    match job["id"].is_null() {
+        true => Err(format!("Cannot erase job: {}", job["message"]).into()),
+        false => {
+            println!("Job {} erased.", id);
+            Ok(())
+        }
+    }
+}
+
+/// Lists manual jobs of given pipelines and plays the ones
+/// chosen by user.
+pub async fn play_manual(gitlab: &Gitlab, pips: &[Pipeline]) -> gitlab::Result<()> {
+    let manual = pips
+        .iter()
+        .flat_map(|p| {
+            p.stages
+                .iter()
+                .flat_map(|s| s.jobs.iter())
+                .filter(|j| "manual" == j.status)
+                .map(move |j| (p, j))
+        })
+        .collect::<Vec<_>>();
+
+    if manual.is_empty() {
+        return Ok(());
+    }
+
+    println!();
+    for (i, (pip, job)) in manual.iter().enumerate() {
+        println!(
+            "{}) {} {} (pipeline {})",
+            i + 1,
+            job.name.0,
+            job.id,
+            pip.id.0
+        );
+    }
+
+    for i in prompt::choose_many("Jobs to play (e.g. 1,3):", manual.len()) {
+        let (_, job) = manual[i];
+        let played = gitlab.post(&format!("jobs/{}/play", job.id), &[]).await?;
+
+        match played["status"].as_str() {
+            Some(status) => println!(
+                "Job {} {} {} {}",
+                job.name.0,
+                job.id,
+                term::symbol("→", "->"),
+                Label(status.to_string()).to_string(status)
+            ),
+            None => {
+                return Err(format!("Cannot play job {}: {}", job.name.0, played["message"]).into())
+            }
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,551 @@
+mod args;
+mod artifacts;
+mod badge;
+mod budgets;
+mod cache;
+mod cancel;
+mod commit;
+mod completions;
+mod config;
+mod deployments;
+mod diff;
+mod envs;
+mod excerpt;
+pub mod exit;
+pub mod gitlab;
+mod hooks;
+pub mod job;
+mod jobs;
+mod lint;
+mod logs;
+mod mr;
+mod netrc;
+mod notify;
+pub mod output;
+pub mod pipeline;
+mod projects;
+mod prompt;
+mod quality;
+mod releases;
+mod report;
+mod retry;
+mod run;
+mod schedules;
+mod security;
+mod sparkline;
+pub mod stage;
+mod stats;
+mod status;
+mod summary;
+mod term;
+mod time;
+mod train;
+mod view;
+mod watch;
+
+/// Data model of pipeline trees - pipelines made of stages
+/// made of jobs. Can be built from Gitlab API JSON (see
+/// `from_json()` constructors) and (de)serialized with serde.
+pub mod model {
+    pub use crate::excerpt::TraceLine;
+    pub use crate::job::Job;
+    pub use crate::pipeline::Pipeline;
+    pub use crate::stage::Stage;
+    pub use crate::Label;
+}
+
+use crate::config::{Config, LocalConfig};
+use crate::gitlab::{Connection, Gitlab};
+use crate::view::View;
+use serde::{Deserialize, Serialize};
+use std::env;
+use std::process;
+use std::time::Duration;
+
+use clap::parser::ValueSource;
+use clap_complete::Shell;
+use colored::*;
+use futures::future::join_all;
+use regex::Regex;
+use url::Url;
+
+const DEFAULT_LIMIT: u8 = 3;
+const SEMAPHORE_LIMIT: usize = 10;
+/// Environment variables holding private token (first wins).
+const TOKEN_VARIABLES: [&str; 3] = ["GLP_PRIVATE_TOKEN", "GITLAB_TOKEN", "GITLAB_PRIVATE_TOKEN"];
+
+// trait Labelable {}
+
+/// Text colored according to a status (pipeline ID, stage or
+/// job name).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Label(pub String);
+
+impl Label {
+    pub fn new(text: &str) -> Label {
+        Label(text.to_string())
+    }
+
+    /// The text (uncolored).
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Colors the text according to given status. Plain
+    /// output spells the status out as well.
+    pub fn to_string(&self, base: &str) -> String {
+        let text = match (base, term::is_plain()) {
+            ("success" | "failed" | "running", true) => format!("{} [{}]", self.0, base),
+            _ => self.0.clone(),
+        };
+
+        match base {
+            "success" => text.green().to_string(),
+            "failed" => text.red().to_string(),
+            "manual" => format!("{} [manual]", text),
+            "running" => text.yellow().to_string(),
+            &_ => text,
+        }
+    }
+
+    /// Width of the text colored by `to_string()` (without
+    /// color codes).
+    pub fn width(&self, base: &str) -> usize {
+        match (base, term::is_plain()) {
+            ("manual", _) => self.0.chars().count() + " [manual]".len(),
+            ("success" | "failed" | "running", true) => {
+                self.0.chars().count() + base.len() + " []".len()
+            }
+            _ => self.0.chars().count(),
+        }
+    }
+}
+
+// Represents Gitlab stage (group of jobs).
+
+/// Runs glp command line app (arguments are taken from the
+/// process).
+pub async fn run() -> gitlab::Result<()> {
+    term::init();
+
+    // 0. Parse arguments.
+    // Settings precedence: params > environment variables >
+    // local config (.glp) > global config.
+    let app_args = args::parse();
+    let config = Config::load(app_args.get_one::<String>("config"))?;
+    let local_config = LocalConfig::load()?;
+    time::set_locale(config.locale.as_deref())?;
+    time::set_duration_format(config.duration_format.as_deref())?;
+    time::set_display(&config.times)?;
+    time::set_iso(*app_args.get_one::<bool>("iso-times").unwrap());
+    // Unicode is dropped automatically on limited terminals
+    // unless configured otherwise.
+    term::set_plain(
+        *app_args.get_one::<bool>("ascii").unwrap()
+            || config.tree.ascii.unwrap_or_else(term::is_limited),
+    );
+
+    // Needs just the config (aliases) - no project nor token.
+    if let Some(("completions", sub_args)) = app_args.subcommand() {
+        completions::print(*sub_args.get_one::<Shell>("shell").unwrap(), &config);
+        return Ok(());
+    }
+
+    let project_id = match app_args
+        .get_one::<String>("project")
+        .cloned()
+        .or_else(|| env::var("GLP_PROJECT").ok())
+        .or_else(|| local_config.project.clone())
+    {
+        Some(id) => config.resolve_alias(id),
+        // Project listing doesn't need any.
+        None if matches!(app_args.subcommand_name(), Some("projects")) => String::new(),
+        None => {
+            return Err(exit::usage(
+                "No project ID (no parameter, GLP_PROJECT nor .glp file).",
+            ))
+        }
+    };
+
+    let project_config = config.project(&project_id);
+    let view = View::new(&app_args, &config, project_config);
+
+    let profile = config.profile(app_args.get_one::<String>("profile"), &project_id)?;
+    // Profile given by param beats everything, profile bound
+    // to the project counts as global config.
+    let host = match app_args.contains_id("profile") {
+        true => profile.and_then(|p| p.host.clone()),
+        false => None,
+    }
+    .or_else(|| env::var("GLP_BASE_URL").ok())
+    // Set in Gitlab CI jobs.
+    .or_else(|| env::var("CI_SERVER_URL").ok())
+    .or_else(|| local_config.host.clone())
+    .or_else(|| profile.and_then(|p| p.host.clone()))
+    .or_else(|| config.host.clone());
+    // Same precedence as for the host - environment variables
+    // beat profile bound to the project.
+    let private_token = match app_args.contains_id("profile") {
+        true => profile.and_then(|p| p.token.clone()),
+        false => None,
+    }
+    .or_else(|| TOKEN_VARIABLES.iter().find_map(|name| env::var(name).ok()))
+    .or_else(|| profile.and_then(|p| p.token.clone()))
+    .or_else(|| {
+        let url = host.as_deref().unwrap_or(gitlab::GITLAB_URL);
+
+        netrc::password(Url::parse(url).ok()?.host_str()?)
+    });
+    let private_token = match private_token {
+        Some(token) => token,
+        None => {
+            return Err(exit::auth(
+                "No Gitlab private token found - set GLP_PRIVATE_TOKEN environment variable.",
+            ))
+        }
+    };
+    let connection = Connection {
+        host,
+        // ALL_PROXY is not picked up by the HTTP client itself.
+        proxy: app_args
+            .get_one::<String>("proxy")
+            .cloned()
+            .or_else(|| env::var("ALL_PROXY").ok())
+            .or_else(|| env::var("all_proxy").ok())
+            .or_else(|| config.connection.proxy.clone()),
+        ca_cert: app_args
+            .get_one::<String>("ca-cert")
+            .or(config.connection.ca_cert.as_ref())
+            .cloned(),
+        client_cert: app_args
+            .get_one::<String>("client-cert")
+            .or(config.connection.client_cert.as_ref())
+            .cloned(),
+        client_key: app_args
+            .get_one::<String>("client-key")
+            .or(config.connection.client_key.as_ref())
+            .cloned(),
+        per_page: config.connection.per_page,
+        insecure: *app_args.get_one::<bool>("insecure").unwrap(),
+    };
+    let mut gitlab = Gitlab::new(&private_token, &project_id, &connection)?;
+
+    // Project given by path - use (cached) numeric ID.
+    if !project_id.is_empty() && project_id.trim().parse::<u64>().is_err() {
+        gitlab.set_project_id(&cache::project_id(&gitlab, &project_id).await?);
+    }
+    let limit = match app_args.value_source("limit") {
+        Some(ValueSource::CommandLine) => *app_args.get_one::<u8>("limit").unwrap(),
+        _ => match env::var("GLP_LIMIT") {
+            Ok(limit) => limit
+                .parse()
+                .map_err(|_| exit::usage("Invalid GLP_LIMIT value."))?,
+            Err(_) => local_config
+                .limit
+                .or(project_config.and_then(|p| p.limit))
+                .or(config.limit)
+                .unwrap_or(DEFAULT_LIMIT),
+        },
+    };
+
+    // Subcommands with their own output.
+    match app_args.subcommand() {
+        Some(("train", sub_args)) => {
+            return train::show(&gitlab, sub_args.get_one::<String>("ref")).await
+        }
+        Some(("schedules", sub_args)) => {
+            return match sub_args.subcommand() {
+                Some(("run", run_args)) => {
+                    schedules::run(
+                        &gitlab,
+                        &view,
+                        *run_args.get_one::<u64>("id").unwrap(),
+                        &config.hooks,
+                    )
+                    .await
+                }
+                _ => schedules::list(&gitlab).await,
+            }
+        }
+        Some(("run", sub_args)) => {
+            let pipeline_id = run::run(
+                &gitlab,
+                sub_args.get_one::<String>("ref"),
+                sub_args.get_one::<String>("vars-file"),
+            )
+            .await?;
+
+            if *sub_args.get_one::<bool>("follow").unwrap() {
+                let status =
+                    watch::follow(&gitlab, &view, &pipeline_id, None, &config.hooks).await?;
+                process::exit(exit::status_code(&status));
+            }
+
+            return Ok(());
+        }
+        Some(("rerun", sub_args)) => {
+            let pipeline_id = run::rerun(
+                &gitlab,
+                sub_args.get_one::<String>("pipeline-id").unwrap(),
+                *sub_args.get_one::<bool>("retry").unwrap(),
+            )
+            .await?;
+
+            if *sub_args.get_one::<bool>("follow").unwrap() {
+                let status =
+                    watch::follow(&gitlab, &view, &pipeline_id, None, &config.hooks).await?;
+                process::exit(exit::status_code(&status));
+            }
+
+            return Ok(());
+        }
+        Some(("retry", sub_args)) => {
+            return retry::failed(
+                &gitlab,
+                &view,
+                sub_args.get_one::<String>("pipeline"),
+                *sub_args.get_one::<bool>("follow").unwrap(),
+                &config.hooks,
+            )
+            .await
+        }
+        Some(("cancel", sub_args)) => {
+            return cancel::redundant(&gitlab, sub_args.get_one::<String>("ref")).await
+        }
+        Some(("lint", sub_args)) => {
+            return lint::lint(&gitlab, sub_args.get_one::<String>("path")).await
+        }
+        Some(("erase", sub_args)) => {
+            return jobs::erase(
+                &gitlab,
+                *sub_args.get_one::<u64>("job-id").unwrap(),
+                *sub_args.get_one::<bool>("yes").unwrap(),
+            )
+            .await
+        }
+        Some(("artifacts", sub_args)) => {
+            return match sub_args.subcommand() {
+                Some(("keep", keep_args)) => {
+                    artifacts::keep(&gitlab, *keep_args.get_one::<u64>("job-id").unwrap()).await
+                }
+                Some(("report", _)) => artifacts::report(&gitlab, limit).await,
+                _ => unreachable!(),
+            }
+        }
+        Some(("logs", sub_args)) => {
+            return logs::download(
+                &gitlab,
+                sub_args.get_one::<String>("pipeline"),
+                *sub_args.get_one::<bool>("failed").unwrap(),
+                sub_args.get_one::<String>("dest").unwrap(),
+            )
+            .await
+        }
+        Some(("grep", sub_args)) => {
+            return logs::grep(
+                &gitlab,
+                sub_args.get_one::<Regex>("pattern").unwrap(),
+                sub_args.get_one::<String>("pipeline"),
+                view.failed_only,
+            )
+            .await
+        }
+        Some(("security", sub_args)) => {
+            return security::summary(&gitlab, sub_args.get_one::<String>("pipeline-id").unwrap())
+                .await
+        }
+        Some(("quality", sub_args)) => {
+            return quality::summary(
+                &gitlab,
+                sub_args.get_one::<String>("pipeline-id").unwrap(),
+                sub_args.get_one::<String>("baseline"),
+            )
+            .await
+        }
+        Some(("diff", sub_args)) => {
+            return diff::tests(
+                &gitlab,
+                sub_args.get_one::<String>("a").unwrap(),
+                sub_args.get_one::<String>("b").unwrap(),
+            )
+            .await
+        }
+        Some(("releases", _)) => return releases::list(&gitlab, limit).await,
+        Some(("watch", sub_args)) => {
+            let options = watch::Options {
+                fullscreen: *sub_args.get_one::<bool>("fullscreen").unwrap(),
+                notify: *sub_args.get_one::<bool>("notify").unwrap(),
+                timeout: sub_args.get_one::<Duration>("timeout").copied(),
+            };
+
+            return watch::watch(&gitlab, &view, limit, &config, &options).await;
+        }
+        Some(("status", sub_args)) => {
+            return status::print(&gitlab, sub_args.get_one::<String>("ref")).await
+        }
+        Some(("wait", sub_args)) => {
+            return status::wait(
+                &gitlab,
+                &view,
+                sub_args.get_one::<String>("ref"),
+                view.job_pattern.as_ref(),
+                &config.hooks,
+                sub_args.get_one::<Duration>("timeout").copied(),
+            )
+            .await
+        }
+        Some(("badge", sub_args)) => {
+            return badge::write(
+                &gitlab,
+                sub_args.get_one::<String>("ref"),
+                *sub_args.get_one::<bool>("coverage").unwrap(),
+                sub_args.get_one::<String>("out").unwrap(),
+            )
+            .await
+        }
+        Some(("check-budgets", sub_args)) => {
+            return budgets::check(
+                &gitlab,
+                sub_args.get_one::<String>("pipeline-id").unwrap(),
+                &config.budgets,
+            )
+            .await
+        }
+        Some(("stats", sub_args)) if *sub_args.get_one::<bool>("stages").unwrap() => {
+            return stats::stages(&gitlab).await
+        }
+        Some(("stats", sub_args)) => {
+            return match &view.job_pattern {
+                Some(pattern) => {
+                    stats::job(
+                        &gitlab,
+                        pattern,
+                        *sub_args.get_one::<bool>("histogram").unwrap(),
+                    )
+                    .await
+                }
+                None => stats::pipelines(&gitlab).await,
+            }
+        }
+        Some(("jobs", sub_args)) => {
+            let statuses = sub_args
+                .get_many::<String>("status")
+                .map(|s| s.cloned().collect::<Vec<_>>())
+                .unwrap_or_default();
+
+            return jobs::list(&gitlab, &statuses, limit).await;
+        }
+        Some(("projects", sub_args)) => {
+            return projects::list(
+                &gitlab,
+                sub_args.get_one::<String>("search"),
+                *sub_args.get_one::<bool>("pick").unwrap(),
+            )
+            .await
+        }
+        Some(("deployments", sub_args)) => {
+            let (action, action_args) = sub_args.subcommand().unwrap();
+
+            return deployments::approve(
+                &gitlab,
+                action_args.get_one::<String>("deployment-id").unwrap(),
+                "approve" == action,
+                action_args.get_one::<String>("comment"),
+            )
+            .await;
+        }
+        Some(("envs", sub_args)) => {
+            return match sub_args.subcommand() {
+                Some(("stop", stop_args)) => {
+                    envs::stop(&gitlab, stop_args.get_one::<String>("name").unwrap()).await
+                }
+                _ => envs::list(&gitlab).await,
+            }
+        }
+        _ => {}
+    }
+
+    // 1. Fetch pipelines.
+    let mut commit_sha = None;
+    let pipelines = match app_args.subcommand() {
+        Some(("mr", sub_args)) => {
+            // Only the latest pipeline is shown for the current
+            // branch merge request.
+            let (iid, limit) = match sub_args.get_one::<u64>("iid") {
+                Some(iid) => (*iid, limit),
+                None => (mr::current_iid(&gitlab).await?, 1),
+            };
+
+            gitlab
+                .get_paged(&format!("merge_requests/{}/pipelines", iid), limit as usize)
+                .await?
+        }
+        Some(("commit", sub_args)) => {
+            let sha = commit::resolve(&gitlab, sub_args.get_one::<String>("sha").unwrap()).await?;
+            let pipelines = gitlab
+                .get_paged(&format!("pipelines?sha={}", sha), limit as usize)
+                .await?;
+
+            commit_sha = Some(sha);
+            pipelines
+        }
+        _ => view.list(&gitlab, limit).await?,
+    };
+
+    // Counts only - no jobs needed.
+    if let Some(("summary", _)) = app_args.subcommand() {
+        summary::print(&pipelines);
+        return Ok(());
+    }
+
+    // 2. Fetch jobs for each pipeline. Streamed output is
+    // printed as pipelines are fetched - unless all of them are
+    // needed at once (commit statuses, reports).
+    if view.is_streamed()
+        && commit_sha.is_none()
+        && !matches!(app_args.subcommand_name(), Some("report"))
+    {
+        view.stream(&gitlab, &pipelines).await;
+        return Ok(());
+    }
+
+    let mut pips = view.fetch(&gitlab, &pipelines).await;
+
+    if let Some(sha) = &commit_sha {
+        commit::add_statuses(&gitlab, sha, &mut pips).await?;
+    }
+
+    view.apply(&mut pips);
+
+    if let Some(("report", sub_args)) = app_args.subcommand() {
+        // Templates may use any detail.
+        join_all(
+            pips.iter_mut()
+                .filter(|p| p.details.is_none())
+                .map(|p| p.fetch_details(&gitlab)),
+        )
+        .await
+        .into_iter()
+        .collect::<gitlab::Result<Vec<_>>>()?;
+
+        print!(
+            "{}",
+            report::render(sub_args.get_one::<String>("template").unwrap(), &pips)?
+        );
+        return Ok(());
+    }
+
+    // 3. Print tree.
+    view.print(&pips);
+
+    if app_args.subcommand().is_none() && *app_args.get_one::<bool>("next-schedules").unwrap() {
+        schedules::print_next(&gitlab).await?;
+    }
+
+    if *app_args.get_one::<bool>("interactive").unwrap() {
+        jobs::play_manual(&gitlab, &pips).await?;
+    }
+
+    Ok(())
+}
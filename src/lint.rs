@@ -0,0 +1,51 @@
+use crate::gitlab::{self, Gitlab};
+use colored::*;
+use std::fs;
+
+const DEFAULT_PATH: &str = ".gitlab-ci.yml";
+
+/// Validates CI config file against the project and prints
+/// errors, warnings, included files and the merged config.
+pub async fn lint(gitlab: &Gitlab, path: Option<&String>) -> gitlab::Result<()> {
+    let path = path.map(|p| p.as_str()).unwrap_or(DEFAULT_PATH);
+    let content = fs::read_to_string(path)?;
+
+    let result = gitlab
+        .post_json(
+            "ci/lint",
+            &json::object! {
+                content: content,
+                include_merged_yaml: true,
+            },
+        )
+        .await?;
+
+    if result["valid"].is_null() {
+        return Err(format!("Cannot lint CI config: {}", result["message"]).into());
+    }
+
+    for include in result["includes"].members() {
+        println!("{} {}", "include:".dimmed(), include["location"]);
+    }
+
+    if let Some(merged_yaml) = result["merged_yaml"].as_str() {
+        println!("{}", "merged config:".dimmed());
+        println!("{}", merged_yaml.trim_end());
+    }
+
+    for warning in result["warnings"].members() {
+        println!("{} {}", "warning:".yellow(), warning);
+    }
+
+    for error in result["errors"].members() {
+        println!("{} {}", "error:".red(), error);
+    }
+
+    if result["valid"].as_bool() != Some(true) {
+        return Err(format!("{} is invalid.", path).into());
+    }
+
+    println!("{} is valid.", path.green());
+
+    Ok(())
+}
@@ -0,0 +1,119 @@
+use crate::gitlab::{self, Gitlab};
+use crate::pipeline::Pipeline;
+use crate::SEMAPHORE_LIMIT;
+use colored::*;
+use futures::stream::{self, StreamExt};
+use json::JsonValue;
+use regex::Regex;
+use std::path::Path;
+use tokio::fs;
+
+/// Makes file name like "5678-test-integration.log" out of
+/// job ID and name.
+fn log_file_name(id: &str, name: &str) -> String {
+    let name = name
+        .chars()
+        .map(
+            |c| match c.is_alphanumeric() || c == '-' || c == '_' || c == '.' {
+                true => c,
+                false => '-',
+            },
+        )
+        .collect::<String>();
+
+    format!("{}-{}.log", id, name)
+}
+
+/// Removes ANSI escape sequences and Gitlab section markers
+/// from job trace.
+pub fn clean_trace(trace: &str) -> String {
+    let pattern =
+        Regex::new(r"\x1b\[[0-9;]*[A-Za-z]|section_(start|end):[0-9]+:[^\r\n]+\r").unwrap();
+
+    pattern.replace_all(trace, "").replace('\r', "")
+}
+
+/// Fetches jobs of given pipeline (latest pipeline by default).
+/// Fetches failed jobs only if `failed` is set.
+pub async fn pipeline_jobs(
+    gitlab: &Gitlab,
+    pipeline_id: Option<&String>,
+    failed: bool,
+) -> gitlab::Result<JsonValue> {
+    let pipeline_id = match pipeline_id {
+        Some(id) => id.to_owned(),
+        None => Pipeline::latest_id(gitlab).await?,
+    };
+    let mut path = format!("pipelines/{}/jobs", pipeline_id);
+
+    if failed {
+        path.push_str("?scope[]=failed");
+    }
+
+    gitlab.get_all(&path).await
+}
+
+/// Downloads traces of pipeline jobs (latest pipeline by
+/// default) into `dest` directory. Downloads traces of failed
+/// jobs only if `failed` is set.
+pub async fn download(
+    gitlab: &Gitlab,
+    pipeline_id: Option<&String>,
+    failed: bool,
+    dest: &str,
+) -> gitlab::Result<()> {
+    let jobs = pipeline_jobs(gitlab, pipeline_id, failed).await?;
+    fs::create_dir_all(dest).await?;
+
+    // Limit concurrent downloads - pipelines may have hundreds
+    // of jobs.
+    let mut downloads = stream::iter(jobs.members().map(|job| async move {
+        let id = job["id"].to_string();
+        let trace = gitlab.get_text(&format!("jobs/{}/trace", id)).await?;
+        let file = Path::new(dest).join(log_file_name(&id, &job["name"].to_string()));
+        fs::write(&file, trace).await?;
+
+        Ok::<_, gitlab::Error>(file)
+    }))
+    .buffer_unordered(SEMAPHORE_LIMIT);
+
+    while let Some(file) = downloads.next().await {
+        println!("{}", file?.display());
+    }
+
+    Ok(())
+}
+
+/// Searches traces of pipeline jobs (latest pipeline by
+/// default) for given pattern and prints matching lines like
+/// `test-unit:123: error: something went wrong`.
+pub async fn grep(
+    gitlab: &Gitlab,
+    pattern: &Regex,
+    pipeline_id: Option<&String>,
+    failed: bool,
+) -> gitlab::Result<()> {
+    let jobs = pipeline_jobs(gitlab, pipeline_id, failed).await?;
+
+    // Fetch traces concurrently but print them in job order.
+    let mut traces = stream::iter(jobs.members().map(|job| async move {
+        let trace = gitlab.get_text(&format!("jobs/{}/trace", job["id"])).await;
+        (job["name"].to_string(), trace)
+    }))
+    .buffered(SEMAPHORE_LIMIT);
+
+    while let Some((name, trace)) = traces.next().await {
+        for (i, line) in clean_trace(&trace?).lines().enumerate() {
+            if pattern.is_match(line) {
+                println!(
+                    "{}:{}: {}",
+                    name.purple(),
+                    (i + 1).to_string().green(),
+                    line
+                );
+            }
+        }
+    }
+
+    Ok(())
+}
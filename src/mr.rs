@@ -0,0 +1,32 @@
+use crate::gitlab::{self, Gitlab};
+use std::process::Command;
+
+/// Reads current branch of the local git repository.
+pub fn current_branch() -> gitlab::Result<String> {
+    let output = Command::new("git")
+        .args(["rev-parse", "--abbrev-ref", "HEAD"])
+        .output()?;
+
+    if !output.status.success() {
+        return Err("Cannot read current git branch.".into());
+    }
+
+    Ok(String::from_utf8(output.stdout)?.trim().to_string())
+}
+
+/// Finds IID of the open merge request for the current
+/// local git branch.
+pub async fn current_iid(gitlab: &Gitlab) -> gitlab::Result<u64> {
+    let branch = current_branch()?;
+    let mrs = gitlab
+        .get(&format!(
+            "merge_requests?state=opened&source_branch={}",
+            gitlab::encode(&branch)
+        ))
+        .await?;
+
+    match mrs[0]["iid"].as_u64() {
+        Some(iid) => Ok(iid),
+        None => Err(format!("No open merge request for branch \"{}\".", branch).into()),
+    }
+}
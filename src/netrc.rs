@@ -0,0 +1,58 @@
+use directories::BaseDirs;
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+/// Returns path to netrc file - `NETRC` environment variable
+/// or `~/.netrc` (`~/_netrc` on Windows unless `.netrc`
+/// exists).
+fn path() -> Option<PathBuf> {
+    if let Ok(path) = env::var("NETRC") {
+        return Some(PathBuf::from(path));
+    }
+
+    let home = BaseDirs::new()?.home_dir().to_path_buf();
+    let path = home.join(".netrc");
+
+    match cfg!(windows) && !path.exists() {
+        true => Some(home.join("_netrc")),
+        false => Some(path),
+    }
+}
+
+/// Finds password of given machine in netrc file (falls back
+/// to the "default" entry). Login is not checked - Gitlab
+/// accepts any with a token as password.
+pub fn password(machine: &str) -> Option<String> {
+    let content = fs::read_to_string(path()?).ok()?;
+    let mut tokens = content.split_whitespace();
+    // Whether current entry is the given machine - `None` for
+    // the "default" entry.
+    let mut current = None;
+    let mut found = None;
+    let mut default = None;
+
+    while let Some(token) = tokens.next() {
+        match token {
+            "machine" => current = Some(tokens.next() == Some(machine)),
+            "default" => current = None,
+            "password" => {
+                let password = tokens.next().map(|p| p.to_string());
+
+                match current {
+                    Some(true) => found = found.or(password),
+                    None => default = default.or(password),
+                    _ => {}
+                }
+            }
+            "login" | "account" => {
+                tokens.next();
+            }
+            // Macros run till an empty line - not used by glp.
+            "macdef" => break,
+            _ => {}
+        }
+    }
+
+    found.or(default)
+}
@@ -0,0 +1,183 @@
+use crate::config::NotifyConfig;
+use crate::pipeline::Pipeline;
+use crate::term;
+use crate::watch::is_terminal;
+use std::collections::HashMap;
+use std::io::{self, Write};
+use std::process::{Command, Stdio};
+
+/// Tracks statuses of refs (or jobs of refs) between
+/// refreshes and alerts on transitions matching the `[notify]`
+/// rules - e.g. when a new pipeline of a ref fails after the
+/// previous one succeeded.
+pub struct Notifier<'a> {
+    config: &'a NotifyConfig,
+    /// Last finished status by ref (or "ref/job name") along
+    /// with ID of the pipeline it comes from.
+    statuses: HashMap<String, (u64, String)>,
+    /// Statuses of the first refresh are recorded only.
+    seeded: bool,
+}
+
+impl<'a> Notifier<'a> {
+    pub fn new(config: &'a NotifyConfig) -> Notifier<'a> {
+        Notifier {
+            config,
+            statuses: HashMap::new(),
+            seeded: false,
+        }
+    }
+
+    /// Compares given pipelines with previously seen ones and
+    /// alerts on matching transitions.
+    pub fn update(&mut self, pips: &[Pipeline]) {
+        for message in self.transitions(pips) {
+            alert(&message);
+        }
+    }
+
+    /// Records finished statuses of given pipelines and
+    /// returns messages of matching transitions. Running
+    /// pipelines (and jobs) are skipped until they finish.
+    fn transitions(&mut self, pips: &[Pipeline]) -> Vec<String> {
+        let mut pips = pips
+            .iter()
+            .filter(|p| self.config.refs.is_empty() || self.config.refs.contains(&p.git_ref))
+            .collect::<Vec<_>>();
+        // Oldest first so the latest pipeline of a ref wins.
+        pips.sort_by_key(|p| p.id.0.parse::<u64>().unwrap_or_default());
+
+        let mut messages = vec![];
+
+        for pip in pips {
+            let id = pip.id.0.parse::<u64>().unwrap_or_default();
+
+            // Without job rules the pipeline status counts.
+            if self.config.jobs.is_empty() {
+                let message = format!("Pipeline {} ({})", pip.id.0, pip.git_ref);
+                messages.extend(self.check(pip.git_ref.clone(), id, &pip.status, &message));
+                continue;
+            }
+
+            for job in pip.stages.iter().flat_map(|s| s.jobs.iter()) {
+                if self.config.jobs.contains(&job.name.0) {
+                    let message = format!(
+                        "Job {} of pipeline {} ({})",
+                        job.name.0, pip.id.0, pip.git_ref
+                    );
+                    messages.extend(self.check(
+                        format!("{}/{}", pip.git_ref, job.name.0),
+                        id,
+                        &job.status,
+                        &message,
+                    ));
+                }
+            }
+        }
+
+        let seeded = self.seeded;
+        self.seeded = true;
+
+        match seeded {
+            true => messages,
+            false => vec![],
+        }
+    }
+
+    /// Records finished status of given pipeline and returns
+    /// alert message if the transition matches. Statuses of
+    /// older pipelines than the recorded one are ignored.
+    fn check(&mut self, key: String, id: u64, status: &str, message: &str) -> Option<String> {
+        if !is_terminal(status) {
+            return None;
+        }
+
+        let previous = match self.statuses.get(&key) {
+            Some((previous_id, _)) if *previous_id > id => return None,
+            Some((_, previous)) => Some(previous.clone()),
+            None => None,
+        };
+        self.statuses.insert(key, (id, status.to_string()));
+
+        match previous {
+            Some(previous) if previous != status && self.matches(&previous, status) => {
+                Some(format!(
+                    "{}: {} {} {}",
+                    message,
+                    previous,
+                    term::symbol("→", "->"),
+                    status
+                ))
+            }
+            _ => None,
+        }
+    }
+
+    /// Checks transition against rules like "success->failed"
+    /// ("*" stands for any status). No rules match everything.
+    fn matches(&self, from: &str, to: &str) -> bool {
+        self.config.transitions.is_empty()
+            || self.config.transitions.iter().any(|rule| {
+                let (rule_from, rule_to) = rule.split_once("->").unwrap_or(("*", rule));
+                let rule_from = rule_from.trim();
+                let rule_to = rule_to.trim();
+
+                (rule_from == "*" || rule_from == from) && (rule_to == "*" || rule_to == to)
+            })
+    }
+}
+
+/// Rings terminal bell and sends desktop notification (if
+/// `notify-send` is available).
+fn alert(message: &str) {
+    print!("\x07");
+    io::stdout().flush().unwrap();
+
+    let _ = Command::new("notify-send")
+        .args(["glp", message])
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pipeline(id: u64, git_ref: &str, status: &str) -> Pipeline {
+        Pipeline::from_json(
+            &json::object! { id: id, ref: git_ref, sha: "abc", status: status, web_url: "" },
+        )
+    }
+
+    #[test]
+    fn alerts_on_transitions_across_pipelines_of_ref() {
+        let config = NotifyConfig {
+            transitions: vec!["success->failed".to_string()],
+            ..NotifyConfig::default()
+        };
+        let mut notifier = Notifier::new(&config);
+
+        assert!(notifier
+            .transitions(&[pipeline(1, "main", "success")])
+            .is_empty());
+        // Running pipeline doesn't count until it finishes.
+        assert!(notifier
+            .transitions(&[
+                pipeline(2, "main", "running"),
+                pipeline(1, "main", "success")
+            ])
+            .is_empty());
+        assert_eq!(
+            vec!["Pipeline 2 (main): success → failed".to_string()],
+            notifier.transitions(&[
+                pipeline(2, "main", "failed"),
+                pipeline(1, "main", "success")
+            ])
+        );
+        // Other refs are tracked separately.
+        assert!(notifier
+            .transitions(&[pipeline(3, "feature", "failed")])
+            .is_empty());
+    }
+}
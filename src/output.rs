@@ -0,0 +1,678 @@
+use crate::artifacts::format_size;
+use crate::excerpt::TraceLine;
+use crate::job::Job;
+use crate::pipeline::Pipeline;
+use crate::stage::Stage;
+use crate::term;
+use crate::time;
+use crate::Label;
+use colored::*;
+use json::JsonValue;
+use ptree::print_config::{OutputKind, StyleWhen};
+use ptree::{PrintConfig, TreeBuilder};
+use regex::Regex;
+use std::time::Duration;
+
+const SECRET_MASK: &str = "*****";
+
+/// Output format of pipelines (picked by `--output` param).
+/// Implement it to add another format.
+pub trait Render {
+    /// Renders pipelines (already fetched and filtered) into
+    /// a string.
+    fn render(&self, pips: &[Pipeline]) -> String;
+}
+
+/// Pipeline trees (the default output).
+pub struct Tree {
+    pub print_config: PrintConfig,
+    /// Pipeline variables matching any of these are masked.
+    pub secret_patterns: Vec<Regex>,
+    pub options: TreeOptions,
+}
+
+/// What pipeline trees show.
+#[derive(Debug, Clone, Default)]
+pub struct TreeOptions {
+    /// Append relative time the pipeline finished (requires
+    /// details).
+    pub show_finished: bool,
+    /// Render successful stages as single lines.
+    pub collapse_success: bool,
+    /// Append web URLs to pipelines and jobs.
+    pub show_urls: bool,
+    /// Pipeline identifier - "id", "iid" or "both".
+    pub id_format: String,
+}
+
+/// CSV - pipelines or their jobs.
+pub struct Csv {
+    pub jobs: bool,
+}
+
+/// JSON array of all pipelines.
+pub struct Json;
+
+/// One JSON object per pipeline per line.
+pub struct Jsonl;
+
+/// The whole hierarchy as YAML.
+pub struct Yaml;
+
+/// Aligned table - one row per pipeline.
+pub struct Table;
+
+/// One line per pipeline with failed jobs.
+pub struct Compact;
+
+impl Render for Tree {
+    /// Styled if stdout is a terminal.
+    fn render(&self, pips: &[Pipeline]) -> String {
+        let mut print_config = self.print_config.clone();
+        let mut out = vec![];
+
+        if print_config.should_style_output(OutputKind::Stdout) {
+            print_config.styled = StyleWhen::Always;
+        }
+
+        for (i, pip) in pips.iter().enumerate() {
+            // Space between pipelines.
+            if i > 0 {
+                out.push(b'\n');
+            }
+
+            ptree::output::write_tree_with(&self.build(pip), &mut out, &print_config).unwrap();
+            out.extend(self.render_variables(pip).as_bytes());
+        }
+
+        String::from_utf8_lossy(&out).to_string()
+    }
+}
+
+impl Tree {
+    /// Builds tree of pipeline, stage, job and trace excerpt
+    /// lines.
+    fn build(&self, pip: &Pipeline) -> ptree::item::StringItem {
+        let mut tree = TreeBuilder::new(self.pipeline_line(pip));
+
+        // Invalid CI config - no stages, just the error.
+        if let Some(errors) = pip.yaml_errors() {
+            tree.add_empty_child(format!("yaml invalid: {}", errors).red().to_string());
+
+            return tree.build();
+        }
+
+        for stage in pip.stages.iter() {
+            tree.begin_child(self.stage_line(stage));
+
+            if !self.is_collapsed(stage) {
+                // Jobs are padded so their durations form a column.
+                let name_width = stage
+                    .jobs
+                    .iter()
+                    .map(|j| j.name.width(&j.status))
+                    .max()
+                    .unwrap_or_default();
+                let duration_width = stage
+                    .jobs
+                    .iter()
+                    .map(|j| j.duration_str().chars().count())
+                    .max()
+                    .unwrap_or_default();
+
+                for job in stage.jobs.iter() {
+                    tree.begin_child(self.job_line(job, name_width, duration_width));
+
+                    for line in job.excerpt.iter() {
+                        tree.add_empty_child(trace_line(line));
+                    }
+
+                    tree.end_child();
+                }
+            }
+
+            tree.end_child();
+        }
+
+        tree.build()
+    }
+
+    /// Producess line like "1234 (main @ 1a2b3c4d) [7m 2s]".
+    fn pipeline_line(&self, pip: &Pipeline) -> String {
+        let mut suffix = String::new();
+
+        // Sum of duration of all pipeline jobs. Truncate units
+        // lower than seconds.
+        if pip.is_finished() {
+            suffix = format!(
+                " [{}]",
+                time::duration(Duration::from_secs(pip.duration().as_secs()))
+            );
+        }
+
+        if let Some(coverage) = pip.coverage() {
+            suffix.push_str(&format!(" [coverage {:.1}%]", coverage));
+        }
+
+        if self.options.show_finished {
+            if let Some(finished_at) = pip.details.as_ref().and_then(|d| d["finished_at"].as_str())
+            {
+                suffix.push_str(&format!(
+                    " [{}]",
+                    time::ago(time::Field::Finished, finished_at)
+                ));
+            }
+        }
+
+        if let Some(sparkline) = &pip.sparkline {
+            suffix.push_str(&format!(" {}", sparkline));
+        }
+
+        if self.options.show_urls && !pip.web_url.is_empty() {
+            suffix.push_str(&format!(" {}", pip.web_url.dimmed()));
+        }
+
+        let mut git_ref = git_ref(pip);
+
+        if !pip.sha.is_empty() {
+            git_ref.push_str(&format!(" @ {}", pip.short_sha()));
+        }
+
+        let id = match (&pip.iid, self.options.id_format.as_str()) {
+            (Some(iid), "iid") => Label(format!("#{}", iid)),
+            (Some(iid), "both") => Label(format!("{} #{}", pip.id.0, iid)),
+            _ => pip.id.clone(),
+        };
+        let name = match pip.name() {
+            Some(name) => format!(" {}", name.bold()),
+            None => String::new(),
+        };
+
+        format!(
+            "{}{} ({}){}",
+            &id.to_string(&pip.status),
+            name,
+            git_ref,
+            suffix
+        )
+    }
+
+    /// Successful stage is collapsed into a single line
+    /// if requested.
+    fn is_collapsed(&self, stage: &Stage) -> bool {
+        self.options.collapse_success && "success" == stage.find_status()
+    }
+
+    /// Producess line like "test ✓ (4 jobs, 3m)" for collapsed
+    /// stages - the summary is the number of stage jobs and sum
+    /// of their durations.
+    fn stage_line(&self, stage: &Stage) -> String {
+        let mut suffix = String::new();
+
+        if self.is_collapsed(stage) {
+            let sum = stage
+                .jobs
+                .iter()
+                .filter_map(|j| j.duration)
+                .sum::<Duration>();
+
+            suffix = format!(
+                "{} ({} {}, {})",
+                term::symbol(" ✓", ""),
+                stage.jobs.len(),
+                if stage.jobs.len() == 1 { "job" } else { "jobs" },
+                time::duration(Duration::from_secs(sum.as_secs()))
+            );
+        }
+
+        if stage.declared_only {
+            suffix = format!(" {}", "(no jobs)".dimmed());
+        }
+
+        format!("{}{}", stage.name.to_string(stage.find_status()), suffix)
+    }
+
+    /// Producess line like "build (7m 2s) -> production" with
+    /// name and duration padded to given widths.
+    fn job_line(&self, job: &Job, name_width: usize, duration_width: usize) -> String {
+        let padding = " ".repeat(name_width.saturating_sub(job.name.width(&job.status)));
+        let mut suffix = String::new();
+
+        if let Some(environment) = &job.environment {
+            suffix.push_str(&format!(" {} {}", term::symbol("→", "->"), environment));
+        }
+
+        if let Some(blocked) = &job.blocked {
+            suffix.push_str(&format!(" {}", format!("[{}]", blocked).yellow()));
+        }
+
+        if let Some(size) = job.artifacts_size {
+            suffix.push_str(&format!(" [artifacts {}]", format_size(size)));
+        }
+
+        if let Some(downstream) = &job.downstream {
+            suffix.push_str(&format!(" {} {}", term::symbol("→", "->"), downstream));
+        }
+
+        if self.options.show_urls && !job.web_url.is_empty() {
+            suffix.push_str(&format!(" {}", job.web_url.dimmed()));
+        }
+
+        format!(
+            "{}{} ({:>width$}{}){}",
+            &job.name.to_string(&job.status),
+            padding,
+            job.duration_str(),
+            job.coverage
+                .map(|c| format!(", {:.1}%", c))
+                .unwrap_or_default(),
+            suffix,
+            width = duration_width
+        )
+    }
+
+    /// Renders pipeline variables below the pipeline tree
+    /// (masking secrets).
+    fn render_variables(&self, pip: &Pipeline) -> String {
+        let mut out = String::new();
+        let variables = match &pip.variables {
+            Some(variables) => variables,
+            None => return out,
+        };
+
+        for variable in variables.members() {
+            let key = variable["key"].as_str().unwrap_or("");
+            let value = variable["value"].as_str().unwrap_or("");
+            let masked = self
+                .secret_patterns
+                .iter()
+                .any(|p| p.is_match(key) || p.is_match(value));
+
+            out.push_str(&format!(
+                "  ${}={}\n",
+                key,
+                if masked { SECRET_MASK } else { value }
+            ));
+        }
+
+        out
+    }
+}
+
+/// Aligns rows (the first one is the header) into columns
+/// separated by two spaces. Cells of given column are colored
+/// by their status (spelled out statuses need no label in
+/// plain mode). Cells are padded after formatting so colors
+/// don't break the alignment.
+pub fn table<const N: usize>(rows: &[[String; N]], status_column: usize) -> String {
+    let mut widths = [0; N];
+
+    for row in rows.iter() {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+
+    let mut out = String::new();
+
+    for (i, row) in rows.iter().enumerate() {
+        let line = row
+            .iter()
+            .zip(widths)
+            .enumerate()
+            .map(|(column, (cell, width))| {
+                let padding = " ".repeat(width - cell.chars().count());
+
+                match (i, column == status_column, term::is_plain()) {
+                    // Header is not colored, manual label would
+                    // repeat the status.
+                    (1.., true, false) if cell != "manual" => {
+                        format!("{}{}", Label(cell.clone()).to_string(cell), padding)
+                    }
+                    _ => format!("{}{}", cell, padding),
+                }
+            })
+            .collect::<Vec<_>>()
+            .join("  ");
+
+        out.push_str(line.trim_end());
+        out.push('\n');
+    }
+
+    out
+}
+
+/// Merge request (like "!512 Add rate limiter") of merge
+/// request pipelines instead of their
+/// "refs/merge-requests/512/head" ref.
+fn git_ref(pip: &Pipeline) -> String {
+    match &pip.merge_request {
+        Some(merge_request) => merge_request.clone(),
+        None => pip.git_ref.clone(),
+    }
+}
+
+/// Pipeline duration like "7m 2s" - "-" until it finishes.
+fn duration(pip: &Pipeline) -> String {
+    match pip.is_finished() {
+        true => time::duration(Duration::from_secs(pip.duration().as_secs())),
+        false => "-".to_string(),
+    }
+}
+
+/// Matched trace lines are red, lines of the trace tail dimmed.
+fn trace_line(line: &TraceLine) -> String {
+    match line.matched {
+        true => line.text.red().to_string(),
+        false => line.text.dimmed().to_string(),
+    }
+}
+
+impl Render for Csv {
+    fn render(&self, pips: &[Pipeline]) -> String {
+        csv(pips, self.jobs)
+    }
+}
+
+impl Render for Json {
+    fn render(&self, pips: &[Pipeline]) -> String {
+        let pips = pips.iter().map(|p| p.to_json()).collect::<Vec<_>>();
+
+        format!("{}\n", json::stringify_pretty(pips, 2))
+    }
+}
+
+impl Render for Jsonl {
+    fn render(&self, pips: &[Pipeline]) -> String {
+        pips.iter()
+            .map(|p| format!("{}\n", p.to_json().dump()))
+            .collect()
+    }
+}
+
+impl Render for Yaml {
+    fn render(&self, pips: &[Pipeline]) -> String {
+        yaml(pips)
+    }
+}
+
+impl Render for Table {
+    fn render(&self, pips: &[Pipeline]) -> String {
+        let mut rows = vec![["ID", "STATUS", "REF", "SHA", "DURATION", "JOBS"].map(String::from)];
+
+        for pip in pips {
+            let jobs = pip.stages.iter().map(|s| s.jobs.len()).sum::<usize>();
+
+            rows.push([
+                pip.id.0.clone(),
+                pip.status.clone(),
+                git_ref(pip),
+                pip.short_sha().to_string(),
+                duration(pip),
+                jobs.to_string(),
+            ]);
+        }
+
+        table(&rows, 1)
+    }
+}
+
+impl Render for Compact {
+    /// Produces lines like
+    /// "1234 failed main @ 1a2b3c4d [3m 5s] test, lint".
+    fn render(&self, pips: &[Pipeline]) -> String {
+        let mut out = String::new();
+
+        for pip in pips {
+            let failed = pip
+                .stages
+                .iter()
+                .flat_map(|s| s.jobs.iter())
+                .filter(|j| "failed" == j.status)
+                .map(|j| j.name.0.as_str())
+                .collect::<Vec<_>>();
+
+            out.push_str(&format!(
+                "{} {} @ {} [{}]",
+                pip.id.to_string(&pip.status),
+                git_ref(pip),
+                pip.short_sha(),
+                duration(pip)
+            ));
+
+            if !failed.is_empty() {
+                out.push_str(&format!(" {}", failed.join(", ")));
+            }
+
+            out.push('\n');
+        }
+
+        out
+    }
+}
+
+/// Escapes CSV field (quotes it if needed).
+fn csv_field(value: &str) -> String {
+    match value.contains([',', '"', '\n']) {
+        true => format!("\"{}\"", value.replace('"', "\"\"")),
+        false => value.to_string(),
+    }
+}
+
+/// Formats CSV row (with line break).
+fn csv_row(fields: &[String]) -> String {
+    format!(
+        "{}\n",
+        fields
+            .iter()
+            .map(|f| csv_field(f))
+            .collect::<Vec<_>>()
+            .join(",")
+    )
+}
+
+/// Formats pipelines (or their jobs if `jobs` is set) as CSV
+/// with a header row. Durations are in seconds.
+fn csv(pips: &[Pipeline], jobs: bool) -> String {
+    let mut out = String::new();
+
+    if jobs {
+        out += &csv_row(
+            &[
+                "pipeline_id",
+                "id",
+                "stage",
+                "name",
+                "status",
+                "duration",
+                "started_at",
+            ]
+            .map(String::from),
+        );
+
+        for pip in pips {
+            for job in pip.stages.iter().flat_map(|s| s.jobs.iter()) {
+                out += &csv_row(&[
+                    pip.id.0.clone(),
+                    job.id.clone(),
+                    job.stage.clone(),
+                    job.name.0.clone(),
+                    job.status.clone(),
+                    job.duration
+                        .map(|d| d.as_secs().to_string())
+                        .unwrap_or_default(),
+                    job.started_at
+                        .as_deref()
+                        .map(time::timestamp)
+                        .unwrap_or_default(),
+                ]);
+            }
+        }
+
+        return out;
+    }
+
+    out += &csv_row(
+        &[
+            "id",
+            "iid",
+            "ref",
+            "sha",
+            "status",
+            "duration",
+            "created_at",
+            "started_at",
+            "finished_at",
+        ]
+        .map(String::from),
+    );
+
+    for pip in pips {
+        let detail = |key: &str| {
+            pip.details
+                .as_ref()
+                .and_then(|d| d[key].as_str())
+                .map(time::timestamp)
+                .unwrap_or_default()
+        };
+
+        out += &csv_row(&[
+            pip.id.0.clone(),
+            pip.iid.clone().unwrap_or_default(),
+            pip.git_ref.clone(),
+            pip.sha.clone(),
+            pip.status.clone(),
+            pip.duration().as_secs().to_string(),
+            detail("created_at"),
+            detail("started_at"),
+            detail("finished_at"),
+        ]);
+    }
+
+    out
+}
+
+/// Formats pipelines with the whole hierarchy as YAML.
+fn yaml(pips: &[Pipeline]) -> String {
+    let pips = JsonValue::Array(pips.iter().map(|p| p.to_json()).collect());
+    let mut out = String::new();
+
+    write_yaml(&pips, 0, &mut out);
+    out
+}
+
+/// Formats scalar (or empty collection) as YAML flow value.
+/// JSON strings are valid double-quoted YAML scalars.
+fn yaml_scalar(value: &JsonValue) -> String {
+    match value {
+        JsonValue::Array(_) => "[]".to_string(),
+        JsonValue::Object(_) => "{}".to_string(),
+        _ => value.dump(),
+    }
+}
+
+/// Whether value is rendered as YAML block (non-empty
+/// collection).
+fn is_block(value: &JsonValue) -> bool {
+    (value.is_array() || value.is_object()) && !value.is_empty()
+}
+
+/// Writes value as block style YAML indented by given number
+/// of spaces.
+fn write_yaml(value: &JsonValue, indent: usize, out: &mut String) {
+    let pad = " ".repeat(indent);
+
+    if value.is_object() {
+        for (key, value) in value.entries() {
+            match is_block(value) {
+                true => {
+                    out.push_str(&format!("{}{}:\n", pad, key));
+                    write_yaml(value, indent + 2, out);
+                }
+                false => out.push_str(&format!("{}{}: {}\n", pad, key, yaml_scalar(value))),
+            }
+        }
+    } else if value.is_array() {
+        for item in value.members() {
+            match (is_block(item), item.is_object()) {
+                // First key goes on the dash line.
+                (true, true) => {
+                    let mut block = String::new();
+                    write_yaml(item, indent + 2, &mut block);
+                    out.push_str(&format!("{}- {}", pad, &block[indent + 2..]));
+                }
+                (true, false) => {
+                    out.push_str(&format!("{}-\n", pad));
+                    write_yaml(item, indent + 2, out);
+                }
+                _ => out.push_str(&format!("{}- {}\n", pad, yaml_scalar(item))),
+            }
+        }
+    } else {
+        out.push_str(&format!("{}{}\n", pad, yaml_scalar(value)));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn json_renders_array_of_pipelines() {
+        let pips = [1, 2].map(|id| {
+            Pipeline::from_json(
+                &json::object! { id: id, ref: "main", sha: "abc", status: "success", web_url: "" },
+            )
+        });
+        let rendered = json::parse(&Json.render(&pips)).unwrap();
+
+        assert_eq!(2, rendered.len());
+        assert_eq!(2, rendered[1]["id"]);
+        assert_eq!(rendered[0], pips[0].to_json());
+    }
+
+    #[test]
+    fn only_declared_stages_are_marked_empty() {
+        let tree = Tree {
+            print_config: PrintConfig::default(),
+            secret_patterns: vec![],
+            options: TreeOptions::default(),
+        };
+        let mut stage = Stage::new("yaml errors", vec![]);
+        assert!(!tree.stage_line(&stage).contains("(no jobs)"));
+
+        stage.declared_only = true;
+        assert!(tree.stage_line(&stage).contains("(no jobs)"));
+    }
+
+    #[test]
+    fn unfinished_merge_request_pipelines_in_table_and_compact() {
+        let mut pip = Pipeline::from_json(&json::object! {
+            id: 1, ref: "refs/merge-requests/512/head", sha: "abc", status: "running", web_url: ""
+        });
+        pip.merge_request = Some("!512 Add rate limiter".to_string());
+        let pips = [pip];
+
+        colored::control::set_override(false);
+        assert_eq!("1 !512 Add rate limiter @ abc [-]\n", Compact.render(&pips));
+        assert!(Table
+            .render(&pips)
+            .ends_with("1   running  !512 Add rate limiter  abc  -         0\n"));
+    }
+
+    #[test]
+    fn table_columns_are_aligned_after_coloring() {
+        let rows = [
+            ["ID", "STATUS", "REF"].map(String::from),
+            ["1", "success", "main"].map(String::from),
+            ["12", "failed", "feature"].map(String::from),
+        ];
+        let escapes = Regex::new(r"\x1b\[[0-9;]*m").unwrap();
+        let rendered = escapes.replace_all(&table(&rows, 1), "").to_string();
+
+        assert_eq!(
+            "ID  STATUS   REF\n1   success  main\n12  failed   feature\n",
+            rendered
+        );
+    }
+}
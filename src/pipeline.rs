@@ -1,68 +1,197 @@
+use crate::excerpt;
+use crate::gitlab::{self, GitlabApi};
+use crate::job::Job;
 use crate::stage::Stage;
+use crate::time;
 use crate::Label;
-use chrono::{offset::Local, DateTime};
-use humantime::format_duration;
 use json::JsonValue;
-use ptree;
-use reqwest;
-use std::borrow::Cow;
-use std::io;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::collections::HashMap;
 use std::time::Duration;
-use timeago;
+
+/// Fetches all stages and their jobs (with needs, environment,
+/// artifacts and downstream pipeline) in a single request.
+/// Stages come in the order defined by the CI config.
+const STAGES_QUERY: &str = "query($path: ID!, $id: CiPipelineID!) {
+  project(fullPath: $path) {
+    pipeline(id: $id) {
+      stages {
+        nodes {
+          name
+          groups {
+            nodes {
+              jobs {
+                nodes {
+                  id name status startedAt duration coverage webPath
+                  needs { nodes { name } }
+                  environment { name }
+                  artifacts { nodes { fileType size } }
+                  downstreamPipeline { id status project { fullPath } }
+                }
+              }
+            }
+          }
+        }
+      }
+    }
+  }
+}";
 
 /// Represents Gitlab pipeline.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Pipeline {
     pub id: Label,
+    /// Project scoped ID (as shown in Gitlab UI).
+    pub iid: Option<String>,
+    /// Name given by `workflow:name` (newer Gitlab only).
+    pub name: Option<String>,
+    pub web_url: String,
+    #[serde(rename = "ref")]
     pub git_ref: String,
+    pub sha: String,
+    /// Merge request ("!512 Add rate limiter") for merge request
+    /// pipelines.
+    pub merge_request: Option<String>,
     pub status: String,
     pub stages: Vec<Stage>,
-    pub show_finished: bool,
+    #[serde(skip)]
     pub details: Option<JsonValue>,
+    #[serde(skip)]
+    pub variables: Option<JsonValue>,
+    /// Durations of recent pipelines of the same ref.
+    pub sparkline: Option<String>,
 }
 
-impl ptree::TreeItem for Pipeline {
-    type Child = Stage;
+impl Pipeline {
+    /// Creates pipeline (without stages) from Gitlab API
+    /// pipeline JSON.
+    pub fn from_json(pip: &JsonValue) -> Pipeline {
+        Pipeline {
+            id: Label(pip["id"].as_usize().unwrap().to_string()),
+            iid: pip["iid"].as_usize().map(|iid| iid.to_string()),
+            name: pip["name"].as_str().map(|name| name.to_string()),
+            web_url: pip["web_url"].as_str().unwrap_or("").to_string(),
+            git_ref: pip["ref"].as_str().unwrap().to_string(),
+            sha: pip["sha"].as_str().unwrap_or("").to_string(),
+            merge_request: None,
+            status: pip["status"].as_str().unwrap().to_string(),
+            stages: vec![],
+            details: None,
+            variables: None,
+            sparkline: None,
+        }
+    }
 
-    fn write_self<W: io::Write>(&self, f: &mut W, _style: &ptree::Style) -> io::Result<()> {
-        let mut suffix = String::new();
+    /// Converts pipeline (with the whole hierarchy) to JSON for
+    /// machine readable outputs.
+    pub fn to_json(&self) -> JsonValue {
+        let detail = |key: &str| {
+            self.details
+                .as_ref()
+                .and_then(|d| d[key].as_str())
+                .map(time::timestamp)
+        };
 
-        if self.is_finished() {
-            suffix = self.get_duration_suffix();
+        json::object! {
+            id: self.id.0.parse::<u64>().ok(),
+            iid: self.iid.as_ref().and_then(|iid| iid.parse::<u64>().ok()),
+            name: self.name(),
+            ref: self.git_ref.clone(),
+            sha: self.sha.clone(),
+            status: self.status.clone(),
+            web_url: self.web_url.clone(),
+            merge_request: self.merge_request.clone(),
+            duration: self.duration().as_secs(),
+            coverage: self.coverage(),
+            created_at: detail("created_at"),
+            started_at: detail("started_at"),
+            finished_at: detail("finished_at"),
+            yaml_errors: self.yaml_errors(),
+            stages: self.stages.iter().map(|s| s.to_json()).collect::<Vec<_>>(),
         }
+    }
 
-        if self.show_finished {
-            if let Some(finished) = self.get_finished_suffix() {
-                suffix.push_str(finished.as_str());
-            }
+    /// Returns CI config errors of a pipeline which failed
+    /// because of them (requires details).
+    pub fn yaml_errors(&self) -> Option<&str> {
+        if !self.stages.is_empty() {
+            return None;
         }
 
-        write!(
-            f,
-            "{} ({}){}",
-            &self.id.to_string(&self.status),
-            &self.git_ref,
-            suffix
-        )
+        self.details.as_ref()?["yaml_errors"].as_str()
     }
 
-    fn children(&self) -> Cow<[Self::Child]> {
-        Cow::from(&self.stages)
+    /// Workflow name - listings may not carry it, details do.
+    pub fn name(&self) -> Option<&str> {
+        self.name
+            .as_deref()
+            .or_else(|| self.details.as_ref()?["name"].as_str())
     }
-}
 
-impl Pipeline {
-    fn is_finished(&self) -> bool {
+    /// First 8 characters of the commit SHA.
+    pub fn short_sha(&self) -> &str {
+        &self.sha[..self.sha.len().min(8)]
+    }
+
+    /// Pipeline has succeeded or failed.
+    pub fn is_finished(&self) -> bool {
         "success" == self.status || "failed" == self.status
     }
 
-    /// Producess output like " [7m 2s]" as a sum of
-    /// duration of all pipeline jobs.
-    /// Truncate units lower than seconds.
-    fn get_duration_suffix(&self) -> String {
+    /// Finds ID of the latest pipeline of the project.
+    pub async fn latest_id(gitlab: &impl GitlabApi) -> gitlab::Result<String> {
+        let pipelines = gitlab.list_pipelines("order_by=id&sort=desc", 1).await?;
+
+        match pipelines[0]["id"].as_usize() {
+            Some(id) => Ok(id.to_string()),
+            None => Err("No pipeline found.".into()),
+        }
+    }
+
+    /// Drops all but failed stages and jobs.
+    pub fn retain_failed(&mut self) {
+        for stage in self.stages.iter_mut() {
+            stage.retain_failed();
+        }
+
+        self.stages.retain(|s| !s.jobs.is_empty());
+    }
+
+    /// Drops all stages not matching given names.
+    pub fn retain_stages(&mut self, names: &[String]) {
+        self.stages.retain(|s| names.contains(&s.name.0));
+    }
+
+    /// Drops all jobs whose names don't match given pattern
+    /// together with stages left empty.
+    pub fn retain_jobs(&mut self, pattern: &Regex) {
+        for stage in self.stages.iter_mut() {
+            stage.retain_jobs(pattern);
+        }
+
+        self.stages.retain(|s| !s.jobs.is_empty());
+    }
+
+    /// Sorts jobs within every stage - see `Stage::sort_jobs()`.
+    pub fn sort_jobs(&mut self, by: &str) {
+        for stage in self.stages.iter_mut() {
+            stage.sort_jobs(by);
+        }
+    }
+
+    /// Sum of duration of all pipeline jobs.
+    pub fn duration(&self) -> Duration {
         let mut sum = Duration::from_secs(0);
 
-        // TODO: fetch from details
+        // No jobs fetched - use pipeline duration from details.
+        if self.stages.is_empty() {
+            if let Some(duration) = self.details.as_ref().and_then(|d| d["duration"].as_u64()) {
+                sum = Duration::from_secs(duration);
+            }
+        }
+
         for stage in self.stages.iter() {
             for job in stage.jobs.iter() {
                 if let Some(dur) = job.duration {
@@ -71,51 +200,384 @@ impl Pipeline {
             }
         }
 
-        format!(
-            " [{}]",
-            format_duration(Duration::from_secs(sum.as_secs())).to_string()
-        )
+        sum
     }
 
-    /// Fetches pipeline details from Gitlab API.
-    pub async fn fetch_details(&mut self, private_token: &str, project_id: &str) {
-        // Fetch jobs for current pipeline.
-        let client = reqwest::Client::new();
-        let response = client
-            .get(format!(
-                "https://gitlab.com/api/v4/projects/{}/pipelines/{}",
-                project_id, &self.id.0
-            ))
-            .header("PRIVATE-TOKEN", private_token)
-            .send()
-            .await
-            .unwrap()
-            .text()
+    /// Pipeline test coverage - the one reported by Gitlab
+    /// (requires details) or average of job coverages.
+    pub fn coverage(&self) -> Option<f64> {
+        let reported = self
+            .details
+            .as_ref()
+            .and_then(|d| d["coverage"].as_str())
+            .and_then(|c| c.parse().ok());
+
+        if reported.is_some() {
+            return reported;
+        }
+
+        let coverages = self
+            .stages
+            .iter()
+            .flat_map(|s| s.jobs.iter())
+            .filter_map(|j| j.coverage)
+            .collect::<Vec<f64>>();
+
+        match coverages.is_empty() {
+            true => None,
+            false => Some(coverages.iter().sum::<f64>() / coverages.len() as f64),
+        }
+    }
+
+    /// Fetches pipeline jobs from Gitlab API and groups
+    /// them into stages.
+    pub async fn fetch_stages(&mut self, gitlab: &impl GitlabApi) -> gitlab::Result<()> {
+        let jobs = gitlab.list_jobs(&self.id.0).await?;
+        // Trigger jobs are not listed among jobs. Bridges
+        // endpoint may be unavailable (e.g. older Gitlab or
+        // insufficient permissions) - there are no trigger jobs
+        // then.
+        let bridges = gitlab
+            .list_bridges(&self.id.0)
             .await
-            .unwrap();
+            .unwrap_or_else(|_| JsonValue::new_array());
+        let mut stages: HashMap<String, Vec<Job>> = HashMap::new();
+
+        let all_jobs = jobs
+            .members()
+            .map(Job::from_json)
+            .chain(bridges.members().map(Job::from_bridge));
+
+        for pip_job in all_jobs {
+            if stages.contains_key(&pip_job.stage) {
+                stages.get_mut(&pip_job.stage).unwrap().push(pip_job);
+            } else {
+                stages.insert(pip_job.stage.clone(), vec![pip_job]);
+            }
+        }
+
+        let mut pip_stages = vec![];
+
+        // Convert hashmap to vec of stages
+        for (stage, jobs) in stages.into_iter() {
+            pip_stages.push(Stage {
+                name: Label(stage),
+                jobs,
+                declared_only: false,
+            });
+        }
+
+        // Sort stages by job "started_at" times.
+        // None are always classifiead as "greater"
+        // so they end up as "last".
+        pip_stages.sort_by(|a, b| {
+            let mut a_jobs = a
+                .jobs
+                .iter()
+                .filter(|j| j.started_at.is_some())
+                .collect::<Vec<&Job>>();
+            a_jobs.sort_by_key(|j| j.started_at.clone());
+
+            let mut b_jobs = b
+                .jobs
+                .iter()
+                .filter(|j| j.started_at.is_some())
+                .collect::<Vec<&Job>>();
+            b_jobs.sort_by_key(|j| j.started_at.clone());
+
+            let a_started_at = match a_jobs.first() {
+                Some(j) => j.started_at.clone(),
+                _ => None,
+            };
+            let b_started_at = match b_jobs.first() {
+                Some(j) => j.started_at.clone(),
+                _ => None,
+            };
+
+            if a_started_at.is_none() {
+                return Ordering::Greater;
+            }
+            if b_started_at.is_none() {
+                return Ordering::Less;
+            }
 
-        self.details = Some(json::parse(&response).unwrap());
+            a_started_at.partial_cmp(&b_started_at).unwrap()
+        });
+
+        self.stages = pip_stages;
+
+        Ok(())
     }
 
-    /// Calculates (if available) relative time when the
-    /// pipeline has finished.
-    /// Producess output like " [2 days ago]".
-    fn get_finished_suffix(&self) -> Option<String> {
-        let finished_at = self.details.as_ref().unwrap()["finished_at"].as_str();
+    /// Adds stages declared in CI config (of the pipeline
+    /// commit) which have no jobs and orders all stages as
+    /// declared. Stages are kept as they are if the config
+    /// cannot be fetched.
+    pub async fn fetch_declared_stages(&mut self, gitlab: &impl GitlabApi) {
+        let lint = match gitlab.ci_lint(&self.sha).await {
+            Ok(lint) => lint,
+            Err(_) => return,
+        };
+        let declared = match lint["merged_yaml"].as_str() {
+            Some(yaml) => declared_stages(yaml),
+            None => return,
+        };
+
+        for name in declared.iter() {
+            if !self.stages.iter().any(|s| &s.name.0 == name) {
+                self.stages.push(Stage {
+                    name: Label(name.clone()),
+                    jobs: vec![],
+                    declared_only: true,
+                });
+            }
+        }
+
+        // Undeclared stages (if any) go last.
+        self.stages.sort_by_key(|s| {
+            declared
+                .iter()
+                .position(|name| name == &s.name.0)
+                .unwrap_or(usize::MAX)
+        });
+    }
 
-        if finished_at.is_some() {
-            let formatter = timeago::Formatter::new();
+    /// Same as `fetch_stages()` but uses Gitlab GraphQL API -
+    /// jobs also come with their `needs`. Project has to be
+    /// given by its full path.
+    pub async fn fetch_stages_graphql(
+        &mut self,
+        gitlab: &impl GitlabApi,
+        project_path: &str,
+    ) -> gitlab::Result<()> {
+        let data = gitlab
+            .graphql(
+                STAGES_QUERY,
+                json::object! {
+                    path: project_path,
+                    id: format!("gid://gitlab/Ci::Pipeline/{}", &self.id.0),
+                },
+            )
+            .await?;
+        let mut stages = vec![];
 
-            return Some(format!(
-                " [{}]",
-                formatter.convert_chrono(
-                    DateTime::parse_from_rfc3339(finished_at.unwrap())
-                        .expect("Cannot parse pipeline \"finished_at\" field."),
-                    Local::now()
-                )
-            ));
+        let nodes = &data["project"]["pipeline"]["stages"]["nodes"];
+
+        if !nodes.is_array() {
+            return Err("Pipeline not found via GraphQL API.".into());
+        }
+
+        for stage in nodes.members() {
+            let name = stage["name"].as_str().unwrap_or_default();
+            let mut jobs = vec![];
+
+            // Jobs are grouped (e.g. parallel jobs) - flatten them.
+            for group in stage["groups"]["nodes"].members() {
+                for job in group["jobs"]["nodes"].members() {
+                    jobs.push(Job::from_graphql(job, name, gitlab.url()));
+                }
+            }
+
+            stages.push(Stage {
+                name: Label(name.to_string()),
+                jobs,
+                declared_only: false,
+            });
         }
 
-        None
+        self.stages = stages;
+
+        Ok(())
+    }
+
+    /// Fetches merge request of merge request pipeline (with
+    /// ref like "refs/merge-requests/512/head").
+    pub async fn fetch_merge_request(&mut self, gitlab: &impl GitlabApi) {
+        let iid = match self
+            .git_ref
+            .strip_prefix("refs/merge-requests/")
+            .and_then(|r| r.split('/').next())
+        {
+            Some(iid) => iid.to_string(),
+            None => return,
+        };
+
+        if let Ok(merge_request) = gitlab.merge_request(&iid).await {
+            if let Some(title) = merge_request["title"].as_str() {
+                self.merge_request = Some(format!("!{} {}", iid, title));
+            }
+        }
+    }
+
+    /// Fetches pipeline details from Gitlab API.
+    pub async fn fetch_details(&mut self, gitlab: &impl GitlabApi) -> gitlab::Result<()> {
+        self.details = Some(gitlab.pipeline_details(&self.id.0).await?);
+
+        Ok(())
+    }
+
+    /// Fetches traces of failed jobs and extracts their most
+    /// relevant lines.
+    pub async fn fetch_excerpts(&mut self, gitlab: &impl GitlabApi, patterns: &[Regex]) {
+        for stage in self.stages.iter_mut() {
+            for job in stage.jobs.iter_mut().filter(|j| "failed" == j.status) {
+                if let Ok(trace) = gitlab.job_trace(&job.id).await {
+                    job.excerpt = excerpt::extract(&trace, patterns);
+                }
+            }
+        }
+    }
+
+    /// Fetches variables the pipeline was triggered with.
+    pub async fn fetch_variables(&mut self, gitlab: &impl GitlabApi) -> gitlab::Result<()> {
+        self.variables = Some(gitlab.pipeline_variables(&self.id.0).await?);
+
+        Ok(())
+    }
+}
+
+/// Picks top level `stages` list out of merged CI config YAML
+/// (as generated by Gitlab - one `- stage` item per line).
+/// Implicit `.pre` and `.post` stages are skipped.
+fn declared_stages(yaml: &str) -> Vec<String> {
+    yaml.lines()
+        .skip_while(|line| line.trim_end() != "stages:")
+        .skip(1)
+        .take_while(|line| line.trim_start().starts_with("- "))
+        .map(|line| {
+            line.trim_start()[2..]
+                .trim()
+                .trim_matches(|c| c == '"' || c == '\'')
+                .to_string()
+        })
+        .filter(|name| !name.starts_with('.'))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gitlab::fake::FakeGitlab;
+
+    fn job(id: usize, name: &str, stage: &str, status: &str, started_at: &str) -> JsonValue {
+        json::object! {
+            id: id,
+            name: name,
+            stage: stage,
+            status: status,
+            web_url: format!("https://gitlab.example.com/jobs/{}", id),
+            started_at: started_at,
+            duration: 12.5,
+        }
+    }
+
+    #[tokio::test]
+    async fn fetch_stages_groups_jobs_in_start_order() {
+        let mut gitlab = FakeGitlab::default();
+        gitlab.jobs.insert(
+            "1".to_string(),
+            json::array![
+                job(11, "deploy", "deploy", "manual", "2024-05-12T14:10:00Z"),
+                job(12, "build", "build", "success", "2024-05-12T14:00:00Z"),
+                job(13, "test", "test", "failed", "2024-05-12T14:05:00Z"),
+            ],
+        );
+        let mut bridge = job(14, "downstream", "test", "success", "2024-05-12T14:06:00Z");
+        bridge["downstream_pipeline"] = json::object! {
+            id: 99,
+            status: "success",
+            web_url: "https://gitlab.example.com/group/other/-/pipelines/99",
+        };
+        gitlab.bridges.insert("1".to_string(), json::array![bridge]);
+
+        let mut pip = Pipeline::from_json(
+            &json::object! { id: 1, iid: 1, ref: "main", sha: "abc", status: "failed", web_url: "" },
+        );
+        pip.fetch_stages(&gitlab).await.unwrap();
+
+        let stages = pip
+            .stages
+            .iter()
+            .map(|s| (s.name.as_str(), s.jobs.len()))
+            .collect::<Vec<_>>();
+        assert_eq!(vec![("build", 1), ("test", 2), ("deploy", 1)], stages);
+
+        let bridge = &pip.stages[1].jobs[1];
+        assert_eq!(
+            Some(12),
+            pip.stages[0].jobs[0].duration.map(|d| d.as_secs())
+        );
+        assert_eq!(
+            Some("group/other"),
+            bridge.downstream.as_deref().map(|d| &d[..11])
+        );
+    }
+
+    #[tokio::test]
+    async fn serde_round_trip_keeps_the_hierarchy() {
+        let mut gitlab = FakeGitlab::default();
+        gitlab.jobs.insert(
+            "1".to_string(),
+            json::array![job(11, "build", "build", "success", "2024-05-12T14:00:00Z")],
+        );
+
+        let mut pip = Pipeline::from_json(
+            &json::object! { id: 1, iid: 1, ref: "main", sha: "abc", status: "failed", web_url: "" },
+        );
+        pip.fetch_stages(&gitlab).await.unwrap();
+        pip.stages[0].jobs[0].excerpt = vec![excerpt::TraceLine {
+            text: "error: no space left".to_string(),
+            matched: true,
+        }];
+
+        let serialized = serde_json::to_string(&pip).unwrap();
+        assert!(serialized.contains(r#""duration":12.5"#));
+        assert_eq!(pip, serde_json::from_str(&serialized).unwrap());
+    }
+
+    #[tokio::test]
+    async fn fetch_stages_graphql_reads_job_fields() {
+        let gitlab = FakeGitlab {
+            graphql: Some(
+                json::parse(
+                    r#"{"project": {"pipeline": {"stages": {"nodes": [{
+                "name": "deploy",
+                "groups": {"nodes": [{"jobs": {"nodes": [{
+                    "id": "gid://gitlab/Ci::Build/21", "name": "production", "status": "MANUAL",
+                    "startedAt": null, "duration": null, "coverage": null, "webPath": null,
+                    "needs": {"nodes": [{"name": "build"}]},
+                    "environment": {"name": "production"},
+                    "artifacts": {"nodes": [{"fileType": "ARCHIVE", "size": "2048"}]},
+                    "downstreamPipeline": {
+                        "id": "gid://gitlab/Ci::Pipeline/99", "status": "SUCCESS",
+                        "project": {"fullPath": "group/other"}
+                    }
+                }]}}]}
+            }]}}}}"#,
+                )
+                .unwrap(),
+            ),
+            ..FakeGitlab::default()
+        };
+
+        let mut pip = Pipeline::from_json(
+            &json::object! { id: 1, iid: 1, ref: "main", sha: "abc", status: "manual", web_url: "" },
+        );
+        pip.fetch_stages_graphql(&gitlab, "group/project")
+            .await
+            .unwrap();
+
+        let job = &pip.stages[0].jobs[0];
+        assert_eq!("21", job.id);
+        assert_eq!("manual", job.status);
+        assert_eq!("", job.web_url);
+        assert_eq!(vec!["build"], job.needs);
+        assert_eq!(Some("production"), job.environment.as_deref());
+        assert_eq!(Some(2048), job.artifacts_size);
+        assert!(job
+            .downstream
+            .as_deref()
+            .unwrap()
+            .starts_with("group/other "));
     }
 }
@@ -0,0 +1,58 @@
+use crate::config::LocalConfig;
+use crate::gitlab::{self, Gitlab};
+use crate::prompt;
+use crate::time::{ago, Field};
+use colored::*;
+
+/// Prints projects the token has access to (most recently
+/// active first), optionally filtered by search term.
+/// Produces output like:
+/// `1. team/backend (1234) - 2 hours ago`.
+/// If `pick` is set, chosen project is written into local
+/// config (`.glp` file).
+pub async fn list(gitlab: &Gitlab, search: Option<&String>, pick: bool) -> gitlab::Result<()> {
+    let mut path = "projects?membership=true&simple=true&order_by=last_activity_at".to_string();
+
+    if let Some(search) = search {
+        path.push_str(&format!("&search={}", gitlab::encode(search)));
+    }
+
+    let projects = gitlab.get_global(&path).await?;
+
+    if projects.is_empty() {
+        println!("No projects found.");
+        return Ok(());
+    }
+
+    for (i, project) in projects.members().enumerate() {
+        println!(
+            "{}. {} {} - {}",
+            i + 1,
+            project["path_with_namespace"],
+            format!("({})", project["id"]).dimmed(),
+            ago(
+                Field::Activity,
+                project["last_activity_at"].as_str().unwrap_or("")
+            )
+        );
+    }
+
+    if !pick {
+        return Ok(());
+    }
+
+    let chosen = match prompt::choose("Project number:", projects.len()) {
+        Some(i) => &projects[i],
+        None => return Err("No project chosen.".into()),
+    };
+    let project = chosen["path_with_namespace"].to_string();
+
+    LocalConfig::save_project(&project)?;
+    println!(
+        "Project {} written into {}.",
+        project.bold(),
+        LocalConfig::PATH
+    );
+
+    Ok(())
+}
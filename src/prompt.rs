@@ -0,0 +1,56 @@
+use std::io::{self, Write};
+
+/// Asks user a yes/no question on the terminal.
+/// Anything but "y"/"yes" means no.
+pub fn confirm(question: &str) -> bool {
+    print!("{} [y/N] ", question);
+    io::stdout().flush().unwrap();
+
+    let mut answer = String::new();
+
+    if io::stdin().read_line(&mut answer).is_err() {
+        return false;
+    }
+
+    matches!(answer.trim().to_lowercase().as_str(), "y" | "yes")
+}
+
+/// Asks user to choose one of `count` numbered items (from 1).
+/// Returns zero based index of the chosen one.
+pub fn choose(question: &str, count: usize) -> Option<usize> {
+    print!("{} ", question);
+    io::stdout().flush().unwrap();
+
+    let mut answer = String::new();
+    io::stdin().read_line(&mut answer).ok()?;
+
+    match answer.trim().parse::<usize>() {
+        Ok(n) if n >= 1 && n <= count => Some(n - 1),
+        _ => None,
+    }
+}
+
+/// Asks user to choose any of `count` numbered items (from 1)
+/// separated by commas or spaces. Returns zero based indexes
+/// of the chosen ones (invalid numbers are skipped).
+pub fn choose_many(question: &str, count: usize) -> Vec<usize> {
+    print!("{} ", question);
+    io::stdout().flush().unwrap();
+
+    let mut answer = String::new();
+
+    if io::stdin().read_line(&mut answer).is_err() {
+        return vec![];
+    }
+
+    let mut chosen = answer
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter_map(|n| n.parse::<usize>().ok())
+        .filter(|n| *n >= 1 && *n <= count)
+        .map(|n| n - 1)
+        .collect::<Vec<_>>();
+
+    chosen.sort();
+    chosen.dedup();
+    chosen
+}
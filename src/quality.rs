@@ -0,0 +1,109 @@
+use crate::artifacts;
+use crate::gitlab::{self, Gitlab};
+use colored::*;
+use json::JsonValue;
+use std::collections::{BTreeMap, HashSet};
+
+const SEVERITIES: [&str; 5] = ["blocker", "critical", "major", "minor", "info"];
+
+/// Fetches code quality issues of given pipeline.
+async fn fetch_issues(gitlab: &Gitlab, pipeline_id: &str) -> gitlab::Result<Vec<JsonValue>> {
+    Ok(
+        artifacts::fetch_reports(gitlab, pipeline_id, &["codequality"])
+            .await?
+            .into_iter()
+            .flat_map(|(_, report)| report.members().cloned().collect::<Vec<_>>())
+            .collect(),
+    )
+}
+
+/// Prints issues grouped by severity and file.
+fn print_issues(issues: &[&JsonValue]) {
+    for severity in SEVERITIES {
+        // File -> issues.
+        let mut files: BTreeMap<String, Vec<&JsonValue>> = BTreeMap::new();
+
+        for issue in issues.iter().filter(|i| i["severity"] == severity) {
+            files
+                .entry(issue["location"]["path"].to_string())
+                .or_default()
+                .push(issue);
+        }
+
+        if files.is_empty() {
+            continue;
+        }
+
+        let count: usize = files.values().map(|f| f.len()).sum();
+        let title = format!("{} ({})", severity, count);
+
+        match severity {
+            "blocker" | "critical" => println!("{}", title.red().bold()),
+            "major" => println!("{}", title.yellow().bold()),
+            _ => println!("{}", title.bold()),
+        }
+
+        for (file, issues) in files {
+            println!("  {}", file);
+
+            for issue in issues {
+                println!(
+                    "    {}: {}",
+                    issue["location"]["lines"]["begin"], issue["description"]
+                );
+            }
+        }
+    }
+}
+
+/// Prints code quality issues of given pipeline. With a
+/// baseline pipeline prints only issues new compared to it and
+/// the number of resolved ones.
+pub async fn summary(
+    gitlab: &Gitlab,
+    pipeline_id: &str,
+    baseline_id: Option<&String>,
+) -> gitlab::Result<()> {
+    let issues = fetch_issues(gitlab, pipeline_id).await?;
+
+    let baseline_id = match baseline_id {
+        Some(id) => id,
+        None => {
+            if issues.is_empty() {
+                println!("No code quality issues in pipeline {}.", pipeline_id);
+            }
+
+            print_issues(&issues.iter().collect::<Vec<_>>());
+            return Ok(());
+        }
+    };
+
+    let baseline = fetch_issues(gitlab, baseline_id).await?;
+    let fingerprints = issues
+        .iter()
+        .map(|i| i["fingerprint"].to_string())
+        .collect::<HashSet<_>>();
+    let baseline_fingerprints = baseline
+        .iter()
+        .map(|i| i["fingerprint"].to_string())
+        .collect::<HashSet<_>>();
+
+    let new = issues
+        .iter()
+        .filter(|i| !baseline_fingerprints.contains(&i["fingerprint"].to_string()))
+        .collect::<Vec<_>>();
+    let resolved = baseline
+        .iter()
+        .filter(|i| !fingerprints.contains(&i["fingerprint"].to_string()))
+        .count();
+
+    println!(
+        "{} new, {} resolved compared to pipeline {}",
+        new.len().to_string().red(),
+        resolved.to_string().green(),
+        baseline_id
+    );
+    print_issues(&new);
+
+    Ok(())
+}
@@ -0,0 +1,77 @@
+use crate::gitlab::{self, Gitlab};
+use crate::term;
+use crate::time::{ago, Field};
+use crate::Label;
+use futures::future::join_all;
+
+/// Finds status of the latest pipeline of given tag
+/// like "1234 success".
+async fn tag_pipeline(gitlab: &Gitlab, tag: &str) -> gitlab::Result<String> {
+    let pipelines = gitlab
+        .get(&format!("pipelines?ref={}&per_page=1", gitlab::encode(tag)))
+        .await?;
+    let pipeline = &pipelines[0];
+
+    Ok(match pipeline["status"].as_str() {
+        Some(status) => Label(pipeline["id"].to_string()).to_string(status),
+        None => "-".to_string(),
+    })
+}
+
+/// Prints recent releases (or tags if the project has no
+/// releases) together with pipelines which built them.
+/// Produces output like:
+/// `v1.2.0 Spring release (2 days ago) → 1234`.
+pub async fn list(gitlab: &Gitlab, limit: u8) -> gitlab::Result<()> {
+    let releases = gitlab.get_paged("releases", limit as usize).await?;
+    let mut lines: Vec<(String, String)> = vec![];
+
+    for release in releases.members() {
+        lines.push((
+            release["tag_name"].to_string(),
+            format!(
+                "{} ({})",
+                release["name"],
+                ago(
+                    Field::Released,
+                    release["released_at"].as_str().unwrap_or("")
+                )
+            ),
+        ));
+    }
+
+    if lines.is_empty() {
+        let tags = gitlab.get_paged("repository/tags", limit as usize).await?;
+
+        for tag in tags.members() {
+            lines.push((
+                tag["name"].to_string(),
+                format!(
+                    "({})",
+                    ago(
+                        Field::Released,
+                        tag["commit"]["created_at"].as_str().unwrap_or("")
+                    )
+                ),
+            ));
+        }
+    }
+
+    if lines.is_empty() {
+        println!("No releases nor tags.");
+    }
+
+    let pipelines = join_all(lines.iter().map(|(tag, _)| tag_pipeline(gitlab, tag))).await;
+
+    for ((tag, description), pipeline) in lines.iter().zip(pipelines) {
+        println!(
+            "{} {} {} {}",
+            tag,
+            description,
+            term::symbol("→", "->"),
+            pipeline?
+        );
+    }
+
+    Ok(())
+}
@@ -0,0 +1,29 @@
+use crate::gitlab;
+use crate::pipeline::Pipeline;
+use std::fs;
+use tera::{Context, Tera};
+
+/// Renders pipelines by given Tera template file. Pipelines
+/// (same as JSON output ones) are available as `pipelines`.
+/// HTML is escaped for `.html` templates.
+pub fn render(path: &str, pips: &[Pipeline]) -> gitlab::Result<String> {
+    let template = fs::read_to_string(path)?;
+    let pipelines = json::JsonValue::Array(pips.iter().map(|p| p.to_json()).collect());
+    let mut context = Context::new();
+
+    context.insert(
+        "pipelines",
+        &serde_json::from_str::<tera::Value>(&pipelines.dump())?,
+    );
+
+    Tera::one_off(&template, &context, path.ends_with(".html"))
+        .map_err(|e| format!("Invalid template {}: {}", path, error_chain(&e)).into())
+}
+
+/// Tera errors carry the cause in their source.
+fn error_chain(error: &dyn std::error::Error) -> String {
+    match error.source() {
+        Some(source) => format!("{} ({})", error, error_chain(source)),
+        None => error.to_string(),
+    }
+}
@@ -0,0 +1,61 @@
+use crate::config::HooksConfig;
+use crate::exit;
+use crate::gitlab::{self, Gitlab};
+use crate::logs;
+use crate::pipeline::Pipeline;
+use crate::view::View;
+use crate::watch;
+use futures::future::join_all;
+use std::process;
+
+/// Retries all failed jobs (except the ones allowed to fail)
+/// of given pipeline (latest pipeline by default). Follows
+/// the pipeline and exits with its result if `follow` is set.
+pub async fn failed(
+    gitlab: &Gitlab,
+    view: &View,
+    pipeline_id: Option<&String>,
+    follow: bool,
+    hooks: &HooksConfig,
+) -> gitlab::Result<()> {
+    let pipeline_id = match pipeline_id {
+        Some(id) => id.to_owned(),
+        None => Pipeline::latest_id(gitlab).await?,
+    };
+    let jobs = logs::pipeline_jobs(gitlab, Some(&pipeline_id), true).await?;
+    let jobs = jobs
+        .members()
+        .filter(|job| !job["allow_failure"].as_bool().unwrap_or(false))
+        .collect::<Vec<_>>();
+
+    if jobs.is_empty() {
+        println!("No failed jobs in pipeline {}.", pipeline_id);
+        return Ok(());
+    }
+
+    let retried =
+        join_all(jobs.iter().map(|job| async move {
+            gitlab.post(&format!("jobs/{}/retry", job["id"]), &[]).await
+        }))
+        .await;
+
+    for (job, retried) in jobs.iter().zip(retried) {
+        let retried = retried?;
+
+        match retried["id"].as_usize() {
+            Some(id) => println!("Job {} {} retried as {}.", job["name"], job["id"], id),
+            None => {
+                return Err(
+                    format!("Cannot retry job {}: {}", job["name"], retried["message"]).into(),
+                )
+            }
+        }
+    }
+
+    if follow {
+        let status = watch::follow(gitlab, view, &pipeline_id, None, hooks).await?;
+        process::exit(exit::status_code(&status));
+    }
+
+    Ok(())
+}
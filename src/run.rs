@@ -0,0 +1,180 @@
+use crate::gitlab::{self, Gitlab};
+use crate::mr;
+use colored::*;
+use json::JsonValue;
+use std::fs;
+
+/// Parses variables file - JSON (`{"KEY": "value"}` or
+/// `[{"key": "KEY", "value": "value"}]`) for `.json` files,
+/// dotenv (`KEY=value` lines) otherwise.
+fn parse_vars_file(path: &str) -> gitlab::Result<Vec<(String, String)>> {
+    let content = fs::read_to_string(path)?;
+    let mut vars = vec![];
+
+    if path.ends_with(".json") {
+        let parsed = json::parse(&content)?;
+
+        for (key, value) in parsed.entries() {
+            vars.push((key.to_string(), value.to_string()));
+        }
+        for var in parsed.members() {
+            vars.push((var["key"].to_string(), var["value"].to_string()));
+        }
+
+        return Ok(vars);
+    }
+
+    for line in content.lines() {
+        let line = line.trim();
+
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let line = line.strip_prefix("export ").unwrap_or(line);
+        let (key, value) = match line.split_once('=') {
+            Some(pair) => pair,
+            None => return Err(format!("Invalid line in variables file: {}", line).into()),
+        };
+        let value = value.trim();
+        let value = match (value.chars().next(), value.chars().last()) {
+            (Some('"'), Some('"')) | (Some('\''), Some('\'')) if value.len() > 1 => {
+                &value[1..value.len() - 1]
+            }
+            _ => value,
+        };
+
+        vars.push((key.trim().to_string(), value.to_string()));
+    }
+
+    Ok(vars)
+}
+
+/// Creates new pipeline for given ref (current git branch by
+/// default) and returns its ID.
+pub async fn run(
+    gitlab: &Gitlab,
+    git_ref: Option<&String>,
+    vars_file: Option<&String>,
+) -> gitlab::Result<String> {
+    let git_ref = match git_ref {
+        Some(git_ref) => git_ref.to_owned(),
+        None => mr::current_branch()?,
+    };
+    let mut variables = JsonValue::new_array();
+
+    if let Some(path) = vars_file {
+        for (key, value) in parse_vars_file(path)? {
+            variables.push(json::object! {
+                key: key,
+                value: value,
+                variable_type: "env_var",
+            })?;
+        }
+    }
+
+    create(gitlab, &git_ref, variables).await
+}
+
+/// Creates new pipeline for given ref with given variables
+/// (in API format) and returns its ID.
+async fn create(gitlab: &Gitlab, git_ref: &str, variables: JsonValue) -> gitlab::Result<String> {
+    let pipeline = gitlab
+        .post_json(
+            "pipeline",
+            &json::object! {
+                "ref": git_ref,
+                variables: variables,
+            },
+        )
+        .await?;
+
+    print_created(&pipeline, git_ref)
+}
+
+/// Reports created pipeline and returns its ID.
+fn print_created(pipeline: &JsonValue, git_ref: &str) -> gitlab::Result<String> {
+    match pipeline["id"].as_usize() {
+        Some(id) => {
+            println!(
+                "Pipeline {} created ({}): {}",
+                id, git_ref, pipeline["web_url"]
+            );
+            Ok(id.to_string())
+        }
+        None => Err(format!("Cannot create pipeline: {}", pipeline["message"]).into()),
+    }
+}
+
+/// Runs given pipeline again - creates new pipeline for the
+/// same ref with the same variables (merge request pipelines
+/// are created for their merge request). With `retry` set the
+/// failed and canceled jobs of the pipeline itself are
+/// retried instead. Returns ID of the resulting pipeline.
+pub async fn rerun(gitlab: &Gitlab, pipeline_id: &str, retry: bool) -> gitlab::Result<String> {
+    if retry {
+        let pipeline = gitlab
+            .post(&format!("pipelines/{}/retry", pipeline_id), &[])
+            .await?;
+
+        return match pipeline["id"].as_usize() {
+            Some(id) => {
+                println!("Pipeline {} retried: {}", id, pipeline["web_url"]);
+                Ok(id.to_string())
+            }
+            None => Err(format!("Cannot retry pipeline: {}", pipeline["message"]).into()),
+        };
+    }
+
+    let pipeline = gitlab.get(&format!("pipelines/{}", pipeline_id)).await?;
+    let git_ref = match pipeline["ref"].as_str() {
+        Some(git_ref) => git_ref.to_string(),
+        None => return Err(format!("Pipeline {} not found.", pipeline_id).into()),
+    };
+
+    // Merge request pipelines have "refs/merge-requests/<iid>/head" ref.
+    if let Some(iid) = git_ref
+        .strip_prefix("refs/merge-requests/")
+        .and_then(|r| r.split('/').next())
+    {
+        let created = gitlab
+            .post(&format!("merge_requests/{}/pipelines", iid), &[])
+            .await?;
+
+        return print_created(&created, &format!("!{}", iid));
+    }
+
+    let mut variables = JsonValue::new_array();
+
+    for variable in gitlab
+        .get(&format!("pipelines/{}/variables", pipeline_id))
+        .await?
+        .members()
+    {
+        variables.push(json::object! {
+            key: variable["key"].clone(),
+            value: variable["value"].clone(),
+            variable_type: variable["variable_type"].clone(),
+        })?;
+    }
+
+    let branch = gitlab
+        .get(&format!("repository/commits/{}", gitlab::encode(&git_ref)))
+        .await?;
+
+    if branch["id"]
+        .as_str()
+        .is_some_and(|sha| pipeline["sha"] != sha)
+    {
+        eprintln!(
+            "{}",
+            format!(
+                "Warning: {} moved on since pipeline {} - the new pipeline runs for {}.",
+                git_ref, pipeline_id, branch["short_id"]
+            )
+            .yellow()
+        );
+    }
+
+    create(gitlab, &git_ref, variables).await
+}
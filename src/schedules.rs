@@ -0,0 +1,115 @@
+use crate::config::HooksConfig;
+use crate::exit;
+use crate::gitlab::{self, Gitlab};
+use crate::time::{countdown, Field};
+use crate::view::View;
+use crate::watch;
+use colored::*;
+use std::process;
+use std::time::Duration;
+
+const NEXT_SCHEDULES: usize = 3;
+const PLAY_ATTEMPTS: usize = 10;
+const PLAY_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Prints project pipeline schedules.
+/// Produces output like:
+/// `12 Nightly build (main) "0 1 * * *" Europe/Prague, next run in 5 hours [@jdoe]`.
+pub async fn list(gitlab: &Gitlab) -> gitlab::Result<()> {
+    let schedules = gitlab.get_all("pipeline_schedules").await?;
+
+    if schedules.is_empty() {
+        println!("No pipeline schedules.");
+    }
+
+    for schedule in schedules.members() {
+        let active = schedule["active"].as_bool().unwrap_or(false);
+        let next_run = match schedule["next_run_at"].as_str() {
+            Some(next_run_at) if active => countdown(Field::NextRun, next_run_at),
+            _ => "-".to_string(),
+        };
+        let line = format!(
+            "{} {} ({}) \"{}\" {}, next run {} [@{}]",
+            schedule["id"],
+            schedule["description"],
+            schedule["ref"],
+            schedule["cron"],
+            schedule["cron_timezone"],
+            next_run,
+            schedule["owner"]["username"]
+        );
+
+        match active {
+            true => println!("{}", line),
+            false => println!("{} {}", line.dimmed(), "[inactive]".dimmed()),
+        }
+    }
+
+    Ok(())
+}
+
+/// Prints footer line with upcoming scheduled pipelines
+/// like "Next scheduled: Nightly build (main) in 5 hours".
+pub async fn print_next(gitlab: &Gitlab) -> gitlab::Result<()> {
+    let schedules = gitlab.get_all("pipeline_schedules?scope=active").await?;
+    let mut upcoming = schedules
+        .members()
+        .filter_map(|s| {
+            s["next_run_at"]
+                .as_str()
+                .map(|next_run_at| (next_run_at.to_string(), s))
+        })
+        .collect::<Vec<_>>();
+
+    if upcoming.is_empty() {
+        return Ok(());
+    }
+
+    // RFC 3339 times with the same offset sort lexicographically.
+    upcoming.sort_by(|a, b| a.0.cmp(&b.0));
+
+    let runs = upcoming
+        .iter()
+        .take(NEXT_SCHEDULES)
+        .map(|(next_run_at, s)| {
+            format!(
+                "{} ({}) {}",
+                s["description"],
+                s["ref"],
+                countdown(Field::NextRun, next_run_at)
+            )
+        })
+        .collect::<Vec<String>>();
+
+    println!();
+    println!("{} {}", "Next scheduled:".dimmed(), runs.join(", "));
+
+    Ok(())
+}
+
+/// Plays pipeline schedule immediately, follows the resulting
+/// pipeline and exits with its result.
+pub async fn run(gitlab: &Gitlab, view: &View, id: u64, hooks: &HooksConfig) -> gitlab::Result<()> {
+    let path = format!("pipeline_schedules/{}", id);
+    let last_pipeline = gitlab.get(&path).await?["last_pipeline"]["id"].as_usize();
+
+    let response = gitlab.post(&format!("{}/play", path), &[]).await?;
+
+    if !response["message"].to_string().starts_with("201") {
+        return Err(format!("Cannot play pipeline schedule: {}", response["message"]).into());
+    }
+
+    // Pipeline is created asynchronously - wait for it.
+    for _ in 0..PLAY_ATTEMPTS {
+        tokio::time::sleep(PLAY_INTERVAL).await;
+
+        let pipeline = gitlab.get(&path).await?["last_pipeline"]["id"].as_usize();
+
+        if let Some(pipeline) = pipeline.filter(|p| Some(*p) != last_pipeline) {
+            let status = watch::follow(gitlab, view, &pipeline.to_string(), None, hooks).await?;
+            process::exit(exit::status_code(&status));
+        }
+    }
+
+    Err("Pipeline schedule was played but no pipeline showed up.".into())
+}
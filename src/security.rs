@@ -0,0 +1,107 @@
+use crate::artifacts;
+use crate::gitlab::{self, Gitlab};
+use colored::*;
+use json::JsonValue;
+use std::collections::HashSet;
+
+const REPORT_TYPES: [&str; 3] = ["sast", "dependency_scanning", "secret_detection"];
+const SEVERITIES: [&str; 6] = ["Critical", "High", "Medium", "Low", "Info", "Unknown"];
+
+/// Identifies the same finding across pipelines (finding
+/// UUIDs are not stable).
+fn fingerprint(vulnerability: &JsonValue) -> String {
+    let location = &vulnerability["location"];
+
+    format!(
+        "{}|{}|{}|{}",
+        vulnerability["name"],
+        location["file"],
+        location["dependency"]["package"]["name"],
+        vulnerability["identifiers"][0]["value"]
+    )
+}
+
+/// Finds latest successful pipeline of the default branch
+/// (other than given one) to compare findings against.
+async fn baseline_pipeline(gitlab: &Gitlab, pipeline_id: &str) -> gitlab::Result<Option<String>> {
+    let project = gitlab.project().await?;
+    let pipelines = gitlab
+        .get(&format!(
+            "pipelines?status=success&ref={}&per_page=2",
+            gitlab::encode(&project["default_branch"].to_string())
+        ))
+        .await?;
+
+    Ok(pipelines
+        .members()
+        .map(|p| p["id"].to_string())
+        .find(|id| id != pipeline_id))
+}
+
+/// Prints severity-bucketed summary of security reports of
+/// given pipeline. Findings not present in the latest default
+/// branch pipeline are counted as new.
+pub async fn summary(gitlab: &Gitlab, pipeline_id: &str) -> gitlab::Result<()> {
+    let reports = artifacts::fetch_reports(gitlab, pipeline_id, &REPORT_TYPES).await?;
+
+    if reports.is_empty() {
+        println!("No security reports in pipeline {}.", pipeline_id);
+        return Ok(());
+    }
+
+    let mut existing = HashSet::new();
+
+    if let Some(baseline_id) = baseline_pipeline(gitlab, pipeline_id).await? {
+        for (_, report) in artifacts::fetch_reports(gitlab, &baseline_id, &REPORT_TYPES).await? {
+            existing.extend(report["vulnerabilities"].members().map(fingerprint));
+        }
+    }
+
+    for report_type in REPORT_TYPES {
+        let vulnerabilities = reports
+            .iter()
+            .filter(|(t, _)| t == report_type)
+            .flat_map(|(_, r)| r["vulnerabilities"].members())
+            .collect::<Vec<&JsonValue>>();
+
+        if vulnerabilities.is_empty() {
+            continue;
+        }
+
+        let new = vulnerabilities
+            .iter()
+            .filter(|v| !existing.contains(&fingerprint(v)))
+            .count();
+        println!(
+            "{}: {} findings ({} new)",
+            report_type.bold(),
+            vulnerabilities.len(),
+            new
+        );
+
+        for severity in SEVERITIES {
+            let bucket = vulnerabilities
+                .iter()
+                .filter(|v| v["severity"].as_str().unwrap_or("Unknown") == severity)
+                .collect::<Vec<_>>();
+
+            if bucket.is_empty() {
+                continue;
+            }
+
+            let new = bucket
+                .iter()
+                .filter(|v| !existing.contains(&fingerprint(v)))
+                .count();
+            let line = format!("  {:<9}{:>4} ({} new)", severity, bucket.len(), new);
+
+            match severity {
+                "Critical" | "High" => println!("{}", line.red()),
+                "Medium" => println!("{}", line.yellow()),
+                _ => println!("{}", line),
+            }
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,55 @@
+use crate::gitlab::{self, GitlabApi};
+use crate::term;
+use futures::future::join_all;
+
+/// Number of pipelines the sparkline is made of.
+const SPARKLINE_PIPELINES: usize = 10;
+const BARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+const ASCII_BARS: [char; 8] = ['_', '.', ',', '-', '~', '=', '+', '#'];
+
+/// Renders values as a sparkline like "▂▃▃▅▇" (or "._.-=" in
+/// plain output).
+pub fn render(values: &[u64]) -> String {
+    let bars = match term::is_plain() {
+        true => ASCII_BARS,
+        false => BARS,
+    };
+    let min = values.iter().min().copied().unwrap_or(0);
+    let max = values.iter().max().copied().unwrap_or(0);
+
+    values
+        .iter()
+        .map(|v| match max - min {
+            0 => bars[0],
+            range => bars[((v - min) * (bars.len() as u64 - 1) / range) as usize],
+        })
+        .collect()
+}
+
+/// Fetches durations (in seconds, oldest first) of recent
+/// finished pipelines of given ref.
+pub async fn durations(gitlab: &impl GitlabApi, git_ref: &str) -> gitlab::Result<Vec<u64>> {
+    let pipelines = gitlab
+        .list_pipelines(
+            &format!("scope=finished&ref={}", gitlab::encode(git_ref)),
+            SPARKLINE_PIPELINES,
+        )
+        .await?;
+
+    // Duration is not part of the listing.
+    let details = join_all(
+        pipelines
+            .members()
+            .map(|p| async move { gitlab.pipeline_details(&p["id"].to_string()).await }),
+    )
+    .await;
+    let mut durations = vec![];
+
+    for detail in details.into_iter().rev() {
+        if let Some(duration) = detail?["duration"].as_u64() {
+            durations.push(duration);
+        }
+    }
+
+    Ok(durations)
+}
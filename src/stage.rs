@@ -1,18 +1,32 @@
-use std::borrow::Cow;
-use std::io;
+use json::JsonValue;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
 
 use crate::job::Job;
 use crate::Label;
-use ptree;
 
-#[derive(Debug, Clone)]
+/// Represents Gitlab stage (group of jobs).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Stage {
     pub name: Label,
     pub jobs: Vec<Job>,
+    /// Declared in CI config but no job matched its rules
+    /// (see `--empty-stages`).
+    #[serde(skip)]
+    pub declared_only: bool,
 }
 
 impl Stage {
-    fn find_status(&self) -> &str {
+    pub fn new(name: &str, jobs: Vec<Job>) -> Stage {
+        Stage {
+            name: Label::new(name),
+            jobs,
+            declared_only: false,
+        }
+    }
+
+    /// Overall status of the stage jobs.
+    pub fn find_status(&self) -> &str {
         // Priorities are:
         // 1. running
         // 2. failed
@@ -36,16 +50,42 @@ impl Stage {
 
         "unknown"
     }
-}
 
-impl ptree::TreeItem for Stage {
-    type Child = Job;
+    /// Converts stage (with jobs) to JSON for machine readable
+    /// outputs.
+    pub fn to_json(&self) -> JsonValue {
+        json::object! {
+            name: self.name.0.clone(),
+            status: self.find_status(),
+            jobs: self.jobs.iter().map(|j| j.to_json()).collect::<Vec<_>>(),
+        }
+    }
+
+    /// Drops all but failed jobs.
+    pub fn retain_failed(&mut self) {
+        self.jobs.retain(|j| "failed" == j.status);
+    }
 
-    fn write_self<W: io::Write>(&self, f: &mut W, _style: &ptree::Style) -> io::Result<()> {
-        write!(f, "{}", &self.name.to_string(self.find_status()))
+    /// Drops all jobs whose names don't match given pattern.
+    pub fn retain_jobs(&mut self, pattern: &Regex) {
+        self.jobs.retain(|j| pattern.is_match(&j.name.0));
     }
 
-    fn children(&self) -> Cow<[Self::Child]> {
-        Cow::from(&self.jobs)
+    /// Sorts jobs by "status" (failures first), "duration"
+    /// (longest first) or "name". Unknown keys keep API order.
+    pub fn sort_jobs(&mut self, by: &str) {
+        match by {
+            "status" => self.jobs.sort_by_key(|j| match j.status.as_str() {
+                "failed" => 0,
+                "running" => 1,
+                "success" => 3,
+                _ => 2,
+            }),
+            "duration" => self
+                .jobs
+                .sort_by_key(|j| std::cmp::Reverse(j.duration.unwrap_or_default())),
+            "name" => self.jobs.sort_by(|a, b| a.name.0.cmp(&b.name.0)),
+            _ => {}
+        }
     }
 }
@@ -0,0 +1,317 @@
+use crate::gitlab::{self, Gitlab};
+use crate::term;
+use crate::time;
+use chrono::DateTime;
+use futures::future::join_all;
+use regex::Regex;
+use std::collections::HashMap;
+use std::time::Duration;
+
+/// Number of recent project jobs searched for runs of given job.
+const STATS_JOBS: usize = 1000;
+/// Number of recent pipelines for pipeline statistics.
+const STATS_PIPELINES: usize = 200;
+/// Number of recent pipelines for stage statistics (all their
+/// jobs are fetched).
+const STAGES_PIPELINES: usize = 30;
+const HISTOGRAM_BUCKETS: u64 = 10;
+const HISTOGRAM_WIDTH: usize = 40;
+
+/// Formats seconds like "2m 4s".
+fn format_secs(secs: u64) -> String {
+    time::duration(Duration::from_secs(secs))
+}
+
+/// Picks percentile (0-100) of sorted values.
+fn percentile(sorted: &[u64], p: usize) -> u64 {
+    match sorted.len() {
+        0 => 0,
+        len => sorted[((len - 1) * p) / 100],
+    }
+}
+
+/// Prints duration statistics of recent finished runs of jobs
+/// matching given pattern (optionally with a histogram).
+/// Produces output like:
+/// `42 runs (3 failed): avg 4m 2s, p50 2m 10s, p95 19m 30s`.
+pub async fn job(gitlab: &Gitlab, pattern: &Regex, histogram: bool) -> gitlab::Result<()> {
+    let jobs = gitlab
+        .get_paged("jobs?scope[]=success&scope[]=failed", STATS_JOBS)
+        .await?;
+    let runs = jobs
+        .members()
+        .filter(|j| pattern.is_match(j["name"].as_str().unwrap_or("")))
+        .collect::<Vec<_>>();
+    let mut durations = runs
+        .iter()
+        .filter_map(|j| j["duration"].as_f64())
+        .map(|d| d as u64)
+        .collect::<Vec<u64>>();
+
+    if durations.is_empty() {
+        println!("No finished runs of jobs matching \"{}\".", pattern);
+        return Ok(());
+    }
+
+    durations.sort();
+
+    println!(
+        "{} runs ({} failed): avg {}, p50 {}, p95 {}",
+        runs.len(),
+        runs.iter().filter(|j| "failed" == j["status"]).count(),
+        format_secs(durations.iter().sum::<u64>() / durations.len() as u64),
+        format_secs(percentile(&durations, 50)),
+        format_secs(percentile(&durations, 95)),
+    );
+
+    if histogram {
+        print_histogram(&durations);
+    }
+
+    Ok(())
+}
+
+/// Prints failure rate, mean time to recovery (from the first
+/// failed pipeline to the next successful one on the same ref)
+/// and the longest failure streak of recent pipelines.
+/// Pipeline finish time is approximated by its update time.
+pub async fn pipelines(gitlab: &Gitlab) -> gitlab::Result<()> {
+    let pipelines = gitlab
+        .get_keyset(
+            "pipelines?scope=finished&order_by=id&sort=desc",
+            STATS_PIPELINES,
+        )
+        .await?;
+
+    // Ref -> (pipeline status, update timestamp), oldest first.
+    let mut refs: HashMap<String, Vec<(String, i64)>> = HashMap::new();
+
+    for pip in pipelines.members().rev() {
+        let status = pip["status"].as_str().unwrap_or("");
+        let updated_at = DateTime::parse_from_rfc3339(pip["updated_at"].as_str().unwrap_or(""));
+
+        if let (true, Ok(updated_at)) = (status == "success" || status == "failed", updated_at) {
+            refs.entry(pip["ref"].to_string())
+                .or_default()
+                .push((status.to_string(), updated_at.timestamp()));
+        }
+    }
+
+    let total = refs.values().map(|p| p.len()).sum::<usize>();
+    let failed = refs
+        .values()
+        .flatten()
+        .filter(|(status, _)| status == "failed")
+        .count();
+
+    if total == 0 {
+        println!("No finished pipelines.");
+        return Ok(());
+    }
+
+    let mut recoveries = vec![];
+    // Length, ref and whether the streak still lasts.
+    let mut longest: Option<(usize, String, bool)> = None;
+
+    for (git_ref, pips) in refs.iter() {
+        // Start time and length of current failure streak.
+        let mut streak: Option<(i64, usize)> = None;
+
+        for (status, updated_at) in pips {
+            streak = match (status.as_str(), streak) {
+                ("failed", None) => Some((*updated_at, 1)),
+                ("failed", Some((start, len))) => Some((start, len + 1)),
+                (_, Some((start, len))) => {
+                    recoveries.push((updated_at - start).max(0) as u64);
+
+                    if longest.as_ref().is_none_or(|l| len > l.0) {
+                        longest = Some((len, git_ref.clone(), false));
+                    }
+
+                    None
+                }
+                _ => None,
+            };
+        }
+
+        if let Some((_, len)) = streak {
+            if longest.as_ref().is_none_or(|l| len > l.0) {
+                longest = Some((len, git_ref.clone(), true));
+            }
+        }
+    }
+
+    println!(
+        "{} pipelines: {} failed ({:.1}%)",
+        total,
+        failed,
+        failed as f64 * 100.0 / total as f64
+    );
+
+    match recoveries.len() {
+        0 => println!("MTTR: -"),
+        count => println!(
+            "MTTR: {} ({} {})",
+            format_secs(recoveries.iter().sum::<u64>() / count as u64),
+            count,
+            if count == 1 { "recovery" } else { "recoveries" }
+        ),
+    }
+
+    match longest {
+        Some((len, git_ref, ongoing)) => println!(
+            "Longest failure streak: {} {} on {}{}",
+            len,
+            if len == 1 { "pipeline" } else { "pipelines" },
+            git_ref,
+            if ongoing { " (ongoing)" } else { "" }
+        ),
+        None => println!("Longest failure streak: -"),
+    }
+
+    Ok(())
+}
+
+/// Prints average and p95 wall-clock duration of each stage
+/// (from its first job start to its last job finish) over
+/// recent successful pipelines, longest first.
+/// Produces output like:
+/// `test  avg 6m 2s  p95 9m 40s  (54%)`.
+pub async fn stages(gitlab: &Gitlab) -> gitlab::Result<()> {
+    let pipelines = gitlab
+        .get_keyset(
+            "pipelines?status=success&order_by=id&sort=desc",
+            STAGES_PIPELINES,
+        )
+        .await?;
+    let jobs = join_all(
+        pipelines
+            .members()
+            .map(|p| async move { gitlab.get_all(&format!("pipelines/{}/jobs", p["id"])).await }),
+    )
+    .await;
+
+    // Stage -> durations in seconds.
+    let mut stages: HashMap<String, Vec<u64>> = HashMap::new();
+
+    for pipeline_jobs in jobs {
+        // Stage -> (first start, last finish).
+        let mut spans: HashMap<String, (i64, i64)> = HashMap::new();
+
+        for job in pipeline_jobs?.members() {
+            let started_at = DateTime::parse_from_rfc3339(job["started_at"].as_str().unwrap_or(""));
+            let finished_at =
+                DateTime::parse_from_rfc3339(job["finished_at"].as_str().unwrap_or(""));
+
+            if let (Ok(started_at), Ok(finished_at)) = (started_at, finished_at) {
+                let span = spans
+                    .entry(job["stage"].to_string())
+                    .or_insert((started_at.timestamp(), finished_at.timestamp()));
+                span.0 = span.0.min(started_at.timestamp());
+                span.1 = span.1.max(finished_at.timestamp());
+            }
+        }
+
+        for (stage, (start, finish)) in spans {
+            stages
+                .entry(stage)
+                .or_default()
+                .push((finish - start).max(0) as u64);
+        }
+    }
+
+    if stages.is_empty() {
+        println!("No successful pipelines.");
+        return Ok(());
+    }
+
+    let mut rows = stages
+        .into_iter()
+        .map(|(stage, mut durations)| {
+            durations.sort();
+            let avg = durations.iter().sum::<u64>() / durations.len() as u64;
+
+            (stage, avg, percentile(&durations, 95))
+        })
+        .collect::<Vec<_>>();
+    rows.sort_by_key(|r| std::cmp::Reverse(r.1));
+
+    let total = rows.iter().map(|r| r.1).sum::<u64>().max(1);
+    let width = rows.iter().map(|r| r.0.chars().count()).max().unwrap();
+
+    for (stage, avg, p95) in rows {
+        println!(
+            "{:<width$}  avg {:<10} p95 {:<10} ({}%)",
+            stage,
+            format_secs(avg),
+            format_secs(p95),
+            avg * 100 / total,
+            width = width
+        );
+    }
+
+    Ok(())
+}
+
+/// Splits sorted durations into buckets starting at the
+/// shortest one. Returns bucket size (in seconds) and counts.
+fn histogram(sorted: &[u64]) -> (u64, Vec<usize>) {
+    let min = sorted[0];
+    let max = sorted[sorted.len() - 1];
+    // Round the size up so the buckets cover the longest
+    // duration as well (at least 1s).
+    let size = (max - min + HISTOGRAM_BUCKETS) / HISTOGRAM_BUCKETS;
+    let mut counts = vec![0; HISTOGRAM_BUCKETS as usize];
+
+    for duration in sorted {
+        let bucket = ((duration - min) / size).min(HISTOGRAM_BUCKETS - 1);
+        counts[bucket as usize] += 1;
+    }
+
+    (size, counts)
+}
+
+/// Prints histogram of sorted durations like:
+/// `   2m -    4m │████████ 12`.
+fn print_histogram(sorted: &[u64]) {
+    let min = sorted[0];
+    let (size, counts) = histogram(sorted);
+    let most = *counts.iter().max().unwrap();
+    let labels = (0..HISTOGRAM_BUCKETS)
+        .map(|i| {
+            format!(
+                "{} - {}",
+                format_secs(min + i * size),
+                format_secs(min + (i + 1) * size)
+            )
+        })
+        .collect::<Vec<_>>();
+    let width = labels.iter().map(|l| l.len()).max().unwrap();
+
+    for (label, count) in labels.iter().zip(counts) {
+        println!(
+            "{:>width$} {}{} {}",
+            label,
+            term::symbol("│", "|"),
+            term::symbol("█", "#").repeat(count * HISTOGRAM_WIDTH / most),
+            count,
+            width = width
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn histogram_buckets_cover_all_durations() {
+        let sorted = [10, 12, 19, 24, 25];
+        let (size, counts) = histogram(&sorted);
+
+        assert_eq!(2, size);
+        assert!(10 + HISTOGRAM_BUCKETS * size > 25);
+        assert_eq!(vec![1, 1, 0, 0, 1, 0, 0, 2, 0, 0], counts);
+        assert_eq!((1, vec![1, 0, 0, 0, 0, 0, 0, 0, 0, 0]), histogram(&[7]));
+    }
+}
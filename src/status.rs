@@ -0,0 +1,62 @@
+use crate::config::HooksConfig;
+use crate::exit;
+use crate::gitlab::{self, Gitlab};
+use crate::view::View;
+use crate::watch;
+use json::JsonValue;
+use regex::Regex;
+use std::process;
+use std::time::Duration;
+
+/// Fetches the latest pipeline (of given ref).
+async fn latest(gitlab: &Gitlab, git_ref: Option<&String>) -> gitlab::Result<JsonValue> {
+    let mut path = "pipelines?per_page=1".to_string();
+
+    if let Some(git_ref) = git_ref {
+        path.push_str(&format!("&ref={}", gitlab::encode(git_ref)));
+    }
+
+    match gitlab.get(&path).await?[0].take() {
+        JsonValue::Null => Err("No pipeline found.".into()),
+        pipeline => Ok(pipeline),
+    }
+}
+
+/// Prints just the status of the latest pipeline (of given
+/// ref) and exits with matching code - see `exit::status_code()`.
+pub async fn print(gitlab: &Gitlab, git_ref: Option<&String>) -> gitlab::Result<()> {
+    let pipeline = latest(gitlab, git_ref).await?;
+    let status = pipeline["status"].as_str().unwrap_or_default();
+
+    println!("{}", status);
+    process::exit(exit::status_code(status));
+}
+
+/// Follows the latest pipeline (of given ref) - or just its
+/// jobs whose whole name matches given pattern (so "deploy"
+/// doesn't wait for "deploy-staging") - until it finishes (or
+/// the timeout elapses) and exits with matching code - see
+/// `exit::status_code()`.
+pub async fn wait(
+    gitlab: &Gitlab,
+    view: &View,
+    git_ref: Option<&String>,
+    job: Option<&Regex>,
+    hooks: &HooksConfig,
+    timeout: Option<Duration>,
+) -> gitlab::Result<()> {
+    let pipeline = latest(gitlab, git_ref).await?;
+    let pipeline_id = pipeline["id"].to_string();
+    let job = job
+        .map(|pattern| Regex::new(&format!("^(?:{})$", pattern)))
+        .transpose()?;
+    let follow = watch::follow(gitlab, view, &pipeline_id, job.as_ref(), hooks);
+    let status = match timeout {
+        Some(timeout) => tokio::time::timeout(timeout, follow)
+            .await
+            .map_err(|_| exit::timeout("Pipeline didn't finish in time."))??,
+        None => follow.await?,
+    };
+
+    process::exit(exit::status_code(&status));
+}
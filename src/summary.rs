@@ -0,0 +1,65 @@
+use json::JsonValue;
+use std::collections::BTreeMap;
+
+use crate::Label;
+
+/// Most severe statuses go first.
+const SEVERITY: [&str; 8] = [
+    "failed", "running", "pending", "created", "manual", "canceled", "skipped", "success",
+];
+
+fn severity(status: &str) -> usize {
+    SEVERITY
+        .iter()
+        .position(|s| *s == status)
+        .unwrap_or(SEVERITY.len())
+}
+
+/// Prints status counts of given pipelines and the worst
+/// status per ref.
+/// Produces output like:
+/// `2 running, 1 failed, 7 success`
+/// `  main: failed`.
+pub fn print(pipelines: &JsonValue) {
+    let mut counts: BTreeMap<usize, (String, usize)> = BTreeMap::new();
+    let mut refs: BTreeMap<String, String> = BTreeMap::new();
+
+    for pip in pipelines.members() {
+        let status = pip["status"].as_str().unwrap_or("unknown");
+
+        counts
+            .entry(severity(status))
+            .or_insert((status.to_string(), 0))
+            .1 += 1;
+
+        let worst = refs
+            .entry(pip["ref"].to_string())
+            .or_insert_with(|| status.to_string());
+
+        if severity(status) < severity(worst) {
+            *worst = status.to_string();
+        }
+    }
+
+    if counts.is_empty() {
+        println!("No pipelines.");
+        return;
+    }
+
+    println!(
+        "{}",
+        counts
+            .values()
+            .map(|(status, count)| format!("{} {}", count, Label(status.clone()).to_string(status)))
+            .collect::<Vec<_>>()
+            .join(", ")
+    );
+
+    for (git_ref, status) in refs {
+        println!(
+            "  {}: {}",
+            git_ref,
+            Label(status.clone()).to_string(&status)
+        );
+    }
+}
@@ -0,0 +1,106 @@
+use std::env;
+use std::sync::OnceLock;
+
+/// ASCII-only output with status words (see `set_plain()`).
+static PLAIN: OnceLock<bool> = OnceLock::new();
+
+/// Whether the console is able to show UTF-8 output (Windows
+/// only - see `init()`).
+static UTF8: OnceLock<bool> = OnceLock::new();
+
+/// Prepares the terminal for glp output. Windows console has
+/// to be switched to ANSI escape codes processing and UTF-8
+/// code page - colors are disabled if that's not possible
+/// (e.g. legacy console). Other systems need no setup.
+pub fn init() {
+    if is_dumb() || !enable_ansi() {
+        colored::control::set_override(false);
+    }
+}
+
+/// Enables ANSI escape codes processing of stdout and stderr
+/// console (if any) and UTF-8 output. Returns whether escape
+/// codes are going to be processed.
+#[cfg(windows)]
+fn enable_ansi() -> bool {
+    use winapi::um::consoleapi::{GetConsoleMode, SetConsoleMode};
+    use winapi::um::processenv::GetStdHandle;
+    use winapi::um::winbase::{STD_ERROR_HANDLE, STD_OUTPUT_HANDLE};
+    use winapi::um::wincon::{SetConsoleOutputCP, ENABLE_VIRTUAL_TERMINAL_PROCESSING};
+    use winapi::um::winnls::CP_UTF8;
+
+    let mut enabled = true;
+
+    for handle in [STD_OUTPUT_HANDLE, STD_ERROR_HANDLE] {
+        unsafe {
+            let handle = GetStdHandle(handle);
+            let mut mode = 0;
+
+            // Redirected to a file or pipe - nothing to set up.
+            if GetConsoleMode(handle, &mut mode) == 0 {
+                continue;
+            }
+            if mode & ENABLE_VIRTUAL_TERMINAL_PROCESSING == 0
+                && SetConsoleMode(handle, mode | ENABLE_VIRTUAL_TERMINAL_PROCESSING) == 0
+            {
+                enabled = false;
+            }
+        }
+    }
+
+    // Without UTF-8 code page Unicode characters are garbled.
+    if unsafe { SetConsoleOutputCP(CP_UTF8) } == 0 {
+        let _ = UTF8.set(false);
+    }
+
+    enabled
+}
+
+#[cfg(not(windows))]
+fn enable_ansi() -> bool {
+    true
+}
+
+/// Detects terminals which cannot render Unicode - dumb ones
+/// (`TERM=dumb`), Windows consoles without UTF-8 code page
+/// and non-UTF-8 locales (like "C" or "en_US.ISO-8859-1" in
+/// `LC_ALL`, `LC_CTYPE` or `LANG`). Unset locale is
+/// considered UTF-8. Expects `init()` to be called first.
+pub fn is_limited() -> bool {
+    if is_dumb() || !UTF8.get().copied().unwrap_or(true) {
+        return true;
+    }
+
+    ["LC_ALL", "LC_CTYPE", "LANG"]
+        .iter()
+        .filter_map(|name| env::var(name).ok())
+        .find(|value| !value.is_empty())
+        .is_some_and(|locale| {
+            let locale = locale.to_lowercase();
+            !locale.contains("utf-8") && !locale.contains("utf8")
+        })
+}
+
+/// Dumb terminals render neither Unicode nor colors.
+pub fn is_dumb() -> bool {
+    env::var("TERM").is_ok_and(|term| term == "dumb")
+}
+
+/// Switches output to ASCII characters - statuses are also
+/// spelled out (like "test [failed]") as colors may not be
+/// available.
+pub fn set_plain(plain: bool) {
+    let _ = PLAIN.set(plain);
+}
+
+pub fn is_plain() -> bool {
+    PLAIN.get().copied().unwrap_or_default()
+}
+
+/// Picks Unicode symbol or its ASCII replacement.
+pub fn symbol(unicode: &'static str, ascii: &'static str) -> &'static str {
+    match is_plain() {
+        true => ascii,
+        false => unicode,
+    }
+}
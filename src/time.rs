@@ -0,0 +1,329 @@
+use crate::config::TimesConfig;
+use crate::exit;
+use crate::gitlab::Result;
+use crate::term;
+use chrono::{offset::Local, DateTime, FixedOffset, SecondsFormat, Utc};
+use humantime::format_duration;
+use std::sync::OnceLock;
+use std::time::Duration;
+use timeago::languages::IsolangLanguage;
+use timeago::{BoxedLanguage, Formatter, TimeUnit};
+
+/// ISO 639-1 code of the language times are formatted in -
+/// English when not set.
+static LOCALE: OnceLock<String> = OnceLock::new();
+
+/// Show RFC 3339 timestamps instead of relative times.
+static ISO: OnceLock<bool> = OnceLock::new();
+
+/// Duration format - see `duration()`.
+static DURATION_FORMAT: OnceLock<String> = OnceLock::new();
+
+const DURATION_FORMATS: [&str; 3] = ["compact", "verbose", "seconds"];
+
+/// Display modes of time fields.
+static DISPLAY: OnceLock<TimesConfig> = OnceLock::new();
+
+const DISPLAY_MODES: [&str; 3] = ["relative", "absolute", "both"];
+
+/// Time fields whose display mode can be configured.
+#[derive(Debug, Clone, Copy)]
+pub enum Field {
+    /// Pipeline finish (`--finished` param).
+    Finished,
+    /// Job start (`jobs` subcommand).
+    Started,
+    /// Latest deployment (`envs` subcommand).
+    Deployed,
+    /// Release or tag (`releases` subcommand).
+    Released,
+    /// Project last activity (`projects` subcommand).
+    Activity,
+    /// Next schedule run (`schedules` subcommand).
+    NextRun,
+}
+
+/// Sets language of relative times and durations (like "de"
+/// or "de_DE.UTF-8"). Languages timeago has no translation
+/// for are reported as errors.
+pub fn set_locale(locale: Option<&str>) -> Result<()> {
+    let Some(locale) = locale else {
+        return Ok(());
+    };
+    let code = locale
+        .split(['_', '-', '.'])
+        .next()
+        .unwrap_or_default()
+        .to_lowercase();
+
+    if language(&code).is_none() {
+        return Err(exit::usage(&format!("Unsupported locale \"{}\".", locale)));
+    }
+
+    let _ = LOCALE.set(code);
+
+    Ok(())
+}
+
+/// Switches all times to RFC 3339 timestamps - see `iso()`.
+pub fn set_iso(iso: bool) {
+    let _ = ISO.set(iso);
+}
+
+fn is_iso() -> bool {
+    ISO.get().copied().unwrap_or_default()
+}
+
+/// Normalizes RFC 3339 time (Gitlab sends various precisions
+/// and offsets) to UTC with seconds like
+/// "2024-05-12T14:03:00Z". Unparseable times are kept.
+pub fn iso(time: &str) -> String {
+    match DateTime::parse_from_rfc3339(time) {
+        Ok(time) => time
+            .with_timezone(&Utc)
+            .to_rfc3339_opts(SecondsFormat::Secs, true),
+        Err(_) => time.to_string(),
+    }
+}
+
+/// Formats time for machine readable outputs - normalized in
+/// `--iso-times` mode, as given by Gitlab otherwise.
+pub fn timestamp(time: &str) -> String {
+    match is_iso() {
+        true => iso(time),
+        false => time.to_string(),
+    }
+}
+
+/// Sets duration format - "compact", "verbose" or "seconds".
+pub fn set_duration_format(format: Option<&str>) -> Result<()> {
+    let Some(format) = format else {
+        return Ok(());
+    };
+
+    if !DURATION_FORMATS.contains(&format) {
+        return Err(exit::usage(&format!(
+            "Invalid duration format \"{}\" (expected one of {}).",
+            format,
+            DURATION_FORMATS.join(", ")
+        )));
+    }
+
+    let _ = DURATION_FORMAT.set(format.to_string());
+
+    Ok(())
+}
+
+/// Sets display modes of time fields - "relative", "absolute"
+/// or "both" (like "2 days ago — 2024-05-12 14:03").
+pub fn set_display(config: &TimesConfig) -> Result<()> {
+    let modes = [
+        &config.display,
+        &config.finished,
+        &config.started,
+        &config.deployed,
+        &config.released,
+        &config.activity,
+        &config.next_run,
+    ];
+
+    if let Some(mode) = modes
+        .iter()
+        .filter_map(|m| m.as_deref())
+        .find(|m| !DISPLAY_MODES.contains(m))
+    {
+        return Err(exit::usage(&format!(
+            "Invalid time display \"{}\" (expected one of {}).",
+            mode,
+            DISPLAY_MODES.join(", ")
+        )));
+    }
+
+    let _ = DISPLAY.set(config.clone());
+
+    Ok(())
+}
+
+/// Display mode of given field - field's own, the default
+/// one or "relative".
+fn display(field: Field) -> &'static str {
+    let Some(config) = DISPLAY.get() else {
+        return "relative";
+    };
+    let mode = match field {
+        Field::Finished => &config.finished,
+        Field::Started => &config.started,
+        Field::Deployed => &config.deployed,
+        Field::Released => &config.released,
+        Field::Activity => &config.activity,
+        Field::NextRun => &config.next_run,
+    };
+
+    mode.as_deref()
+        .or(config.display.as_deref())
+        .unwrap_or("relative")
+}
+
+/// Picks timeago translation of given ISO 639-1 code.
+fn language(code: &str) -> Option<BoxedLanguage> {
+    timeago::from_isolang(IsolangLanguage::from_639_1(code)?)
+}
+
+/// Builds relative time formatter in the configured language.
+fn formatter() -> Formatter<BoxedLanguage> {
+    Formatter::with_language(
+        LOCALE
+            .get()
+            .and_then(|code| language(code))
+            .unwrap_or_else(|| Box::new(timeago::English)),
+    )
+}
+
+/// Formats time of given field according to its display
+/// mode - `relative` formats the relative part.
+/// Missing or unparseable times are shown as "-".
+fn show(field: Field, time: &str, relative: fn(DateTime<FixedOffset>) -> String) -> String {
+    if is_iso() && !time.is_empty() {
+        return iso(time);
+    }
+
+    let Ok(time) = DateTime::parse_from_rfc3339(time) else {
+        return "-".to_string();
+    };
+    let absolute = || {
+        time.with_timezone(&Local)
+            .format("%Y-%m-%d %H:%M")
+            .to_string()
+    };
+
+    match display(field) {
+        "absolute" => absolute(),
+        "both" => format!(
+            "{} {} {}",
+            relative(time),
+            term::symbol("—", "-"),
+            absolute()
+        ),
+        _ => relative(time),
+    }
+}
+
+/// Formats time since given RFC 3339 time like "2 hours ago"
+/// (or the time itself in `--iso-times` mode).
+pub fn ago(field: Field, time: &str) -> String {
+    show(field, time, |time| {
+        formatter().convert_chrono(time, Local::now())
+    })
+}
+
+/// Formats time until given RFC 3339 time like "in 5 hours"
+/// (or the time itself in `--iso-times` mode).
+pub fn countdown(field: Field, time: &str) -> String {
+    show(field, time, until)
+}
+
+/// Formats relative time until given time like "in 5 hours".
+fn until(time: DateTime<FixedOffset>) -> String {
+    let mut formatter = formatter();
+    formatter.ago("");
+
+    let span = formatter.convert_chrono(Local::now(), time);
+    let span = span.trim();
+
+    // Word and whether it goes before the time span.
+    let (word, before) = match LOCALE.get().map(|code| code.as_str()) {
+        Some("fr") => ("dans", true),
+        Some("es") => ("en", true),
+        Some("pt") => ("em", true),
+        Some("it") => ("tra", true),
+        Some("ro") => ("peste", true),
+        Some("ru" | "uk") => ("через", true),
+        Some("be") => ("праз", true),
+        Some("pl") => ("za", true),
+        Some("sv" | "da") => ("om", true),
+        Some("tr") => ("sonra", false),
+        Some("zh") => ("后", false),
+        Some("ja") => ("後", false),
+        _ => ("in", true),
+    };
+
+    match before {
+        true => format!("{} {}", word, span),
+        false => format!("{} {}", span, word),
+    }
+}
+
+/// Formats duration according to the configured format -
+/// "compact" like "7m 2s" (default), "verbose" like
+/// "7 minutes 2 seconds" or "seconds" like "422s". Units are
+/// translated according to the configured language.
+pub fn duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    let parts = [
+        (secs / 86400, TimeUnit::Days),
+        (secs / 3600 % 24, TimeUnit::Hours),
+        (secs / 60 % 60, TimeUnit::Minutes),
+        (secs % 60, TimeUnit::Seconds),
+    ];
+    let nonzero = || parts.iter().filter(|(value, _)| *value > 0);
+
+    match DURATION_FORMAT.get().map(|f| f.as_str()) {
+        Some("seconds") => return format!("{}s", secs),
+        Some("verbose") => {
+            let language = LOCALE
+                .get()
+                .and_then(|code| language(code))
+                .unwrap_or_else(|| Box::new(timeago::English));
+            let formatted = nonzero()
+                .map(|(value, unit)| format!("{} {}", value, language.get_word(*unit, *value)))
+                .collect::<Vec<_>>();
+
+            return match formatted.is_empty() {
+                true => format!("0 {}", language.get_word(TimeUnit::Seconds, 0)),
+                false => formatted.join(" "),
+            };
+        }
+        _ => {}
+    }
+
+    let units = match LOCALE.get().map(|code| code.as_str()) {
+        None | Some("en") => return format_duration(duration).to_string(),
+        Some(code) => units(code),
+    };
+    let formatted = nonzero()
+        .map(|(value, unit)| {
+            let unit = match unit {
+                TimeUnit::Days => units[0],
+                TimeUnit::Hours => units[1],
+                TimeUnit::Minutes => units[2],
+                _ => units[3],
+            };
+
+            format!("{}{}", value, unit)
+        })
+        .collect::<Vec<_>>();
+
+    match formatted.is_empty() {
+        true => format!("0{}", units[3]),
+        false => formatted.join(" "),
+    }
+}
+
+/// Abbreviated day, hour, minute and second units of given
+/// language.
+fn units(code: &str) -> [&'static str; 4] {
+    match code {
+        "de" => [" T", " Std", " Min", " Sek"],
+        "fr" => ["j", "h", "min", "s"],
+        "it" => ["g", "h", "min", "s"],
+        "es" | "pt" | "ro" => ["d", "h", "min", "s"],
+        "ru" | "be" => [" д", " ч", " мин", " с"],
+        "uk" => [" д", " год", " хв", " с"],
+        "pl" => [" d", " godz", " min", " s"],
+        "sv" | "da" => ["d", "t", "m", "s"],
+        "tr" => ["g", "sa", "dk", "sn"],
+        "zh" => ["天", "小时", "分", "秒"],
+        "ja" => ["日", "時間", "分", "秒"],
+        _ => ["d", "h", "m", "s"],
+    }
+}
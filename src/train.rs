@@ -0,0 +1,51 @@
+use crate::gitlab::{self, Gitlab};
+use crate::term;
+use crate::Label;
+use std::collections::HashMap;
+
+/// Prints active merge train(s) - queued merge requests in
+/// their merge order together with merge train pipelines.
+/// Produces output like:
+/// `1. !512 Add rate limiter (main) → 1234 [fresh]`.
+/// Positions are counted within each target branch train.
+pub async fn show(gitlab: &Gitlab, git_ref: Option<&String>) -> gitlab::Result<()> {
+    // Project wide listing cannot be filtered by target branch.
+    let path = match git_ref {
+        Some(git_ref) => format!(
+            "merge_trains/{}?scope=active&sort=asc",
+            gitlab::encode(git_ref)
+        ),
+        None => "merge_trains?scope=active&sort=asc".to_string(),
+    };
+    let cars = gitlab.get_all(&path).await?;
+    let mut positions: HashMap<String, usize> = HashMap::new();
+
+    if cars.is_empty() {
+        println!("Merge train is empty.");
+    }
+
+    for car in cars.members() {
+        let position = positions
+            .entry(car["target_branch"].to_string())
+            .or_default();
+        *position += 1;
+        let pipeline = &car["pipeline"];
+        let pipeline_str = match pipeline["id"].as_usize() {
+            Some(id) => Label(id.to_string()).to_string(pipeline["status"].as_str().unwrap_or("")),
+            None => "-".to_string(),
+        };
+
+        println!(
+            "{}. !{} {} ({}) {} {} [{}]",
+            position,
+            car["merge_request"]["iid"],
+            car["merge_request"]["title"],
+            car["target_branch"],
+            term::symbol("→", "->"),
+            pipeline_str,
+            car["status"]
+        );
+    }
+
+    Ok(())
+}
@@ -0,0 +1,578 @@
+use crate::config::{Config, ProjectConfig};
+use crate::deployments;
+use crate::excerpt;
+use crate::gitlab::{self, GitlabApi};
+use crate::output::{self, Render};
+use crate::pipeline::Pipeline;
+use crate::sparkline;
+use crate::term;
+use crate::SEMAPHORE_LIMIT;
+use clap::parser::ValueSource;
+use clap::ArgMatches;
+use colored::*;
+use futures::future::join_all;
+use futures::stream::{FuturesUnordered, StreamExt};
+use json::JsonValue;
+use ptree::PrintConfig;
+use regex::Regex;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::Semaphore;
+use tokio::task::{JoinError, JoinHandle};
+
+const DEFAULT_SECRET_PATTERN: &str = "(?i)token|password|secret";
+
+/// Represents options for fetching and rendering pipeline
+/// trees shared by all pipeline listings.
+#[derive(Debug, Clone)]
+pub struct View {
+    pub show_finished: bool,
+    pub collapse_success: bool,
+    pub failed_only: bool,
+    /// Pipeline scope - "tags" or "branches".
+    pub scope: Option<String>,
+    pub stage_names: Option<Vec<String>>,
+    pub job_pattern: Option<Regex>,
+    pub depth: String,
+    pub sort: String,
+    pub reverse: bool,
+    pub sort_jobs: Option<String>,
+    pub show_variables: bool,
+    pub secret_patterns: Vec<Regex>,
+    pub show_excerpts: bool,
+    pub graphql: bool,
+    pub show_urls: bool,
+    /// Pipeline identifier - "id", "iid" or "both".
+    pub id_format: String,
+    pub show_sparkline: bool,
+    pub show_empty_stages: bool,
+    /// Output format - "tree", "table", "compact", "csv", "json",
+    /// "jsonl" or "yaml".
+    pub output: String,
+    /// Output jobs instead of pipelines (CSV only).
+    pub job_rows: bool,
+    pub excerpt_patterns: Vec<Regex>,
+    pub print_config: PrintConfig,
+}
+
+impl View {
+    /// Project defaults are used unless given by params.
+    pub fn new(args: &ArgMatches, config: &Config, project: Option<&ProjectConfig>) -> View {
+        let flag = |name: &str, default: fn(&ProjectConfig) -> bool| {
+            *args.get_one::<bool>(name).unwrap() || project.is_some_and(default)
+        };
+        let depth = match (
+            args.value_source("depth"),
+            project.and_then(|p| p.depth.as_ref()),
+        ) {
+            (Some(ValueSource::CommandLine), _) | (_, None) => {
+                args.get_one::<String>("depth").unwrap()
+            }
+            (_, Some(depth)) => depth,
+        }
+        .to_owned();
+        let mut print_config = config.tree.print_config(term::is_plain());
+
+        print_config.depth = match depth.as_str() {
+            "pipelines" => 0,
+            "stages" => 1,
+            _ => print_config.depth,
+        };
+
+        View {
+            show_finished: flag("finished", |p| p.finished),
+            collapse_success: flag("collapse-success", |p| p.collapse_success),
+            failed_only: flag("failed-only", |p| p.failed_only),
+            scope: match (
+                *args.get_one::<bool>("tags-only").unwrap(),
+                *args.get_one::<bool>("branches-only").unwrap(),
+            ) {
+                (true, _) => Some("tags".to_string()),
+                (_, true) => Some("branches".to_string()),
+                _ => None,
+            },
+            stage_names: args
+                .get_many::<String>("stage")
+                .map(|names| names.cloned().collect()),
+            job_pattern: args.get_one::<Regex>("job").cloned(),
+            depth,
+            sort: args.get_one::<String>("sort").unwrap().to_owned(),
+            reverse: *args.get_one::<bool>("reverse").unwrap(),
+            sort_jobs: args
+                .get_one::<String>("sort-jobs")
+                .or(project.and_then(|p| p.sort_jobs.as_ref()))
+                .or(config.tree.sort_jobs.as_ref())
+                .cloned(),
+            show_variables: *args.get_one::<bool>("variables").unwrap(),
+            secret_patterns: config
+                .variables
+                .secret_patterns
+                .clone()
+                .unwrap_or_else(|| vec![DEFAULT_SECRET_PATTERN.to_string()])
+                .iter()
+                .map(|p| Regex::new(p).expect("validated by Config::load()"))
+                .collect(),
+            show_excerpts: *args.get_one::<bool>("excerpts").unwrap(),
+            graphql: *args.get_one::<bool>("graphql").unwrap(),
+            show_urls: *args.get_one::<bool>("urls").unwrap(),
+            id_format: match *args.get_one::<bool>("show-iid").unwrap() {
+                true => "iid".to_string(),
+                false => args.get_one::<String>("id-format").unwrap().to_owned(),
+            },
+            show_sparkline: *args.get_one::<bool>("sparkline").unwrap(),
+            show_empty_stages: *args.get_one::<bool>("empty-stages").unwrap(),
+            output: args.get_one::<String>("output").unwrap().to_owned(),
+            job_rows: *args.get_one::<bool>("jobs").unwrap(),
+            excerpt_patterns: match &config.excerpts.patterns {
+                Some(patterns) => patterns.to_owned(),
+                None => excerpt::DEFAULT_PATTERNS.map(|p| p.to_string()).to_vec(),
+            }
+            .iter()
+            .map(|p| Regex::new(p).expect("validated by Config::load()"))
+            .collect(),
+            print_config,
+        }
+    }
+
+    /// Fetches listing of recent project pipelines (according
+    /// to filters and sorting).
+    pub async fn list(&self, gitlab: &impl GitlabApi, limit: u8) -> gitlab::Result<JsonValue> {
+        let mut query = String::new();
+
+        if self.failed_only {
+            query.push_str("status=failed&");
+        }
+        if let Some(scope) = &self.scope {
+            query.push_str(&format!("scope={}&", scope));
+        }
+
+        // Duration is not supported by the API - sorted
+        // client-side. Always the most recent pipelines are
+        // fetched, `--reverse` is applied to them client-side.
+        let order_by = match self.sort.as_str() {
+            "updated" => "updated_at",
+            "status" => "status",
+            _ => "id",
+        };
+        query.push_str(&format!("order_by={}&sort=desc", order_by));
+
+        gitlab.list_pipelines(&query, limit as usize).await
+    }
+
+    /// Fetches jobs (and details if needed) for each pipeline
+    /// of given pipeline list.
+    pub async fn fetch<G>(&self, gitlab: &G, pipelines: &JsonValue) -> Vec<Pipeline>
+    where
+        G: GitlabApi + Clone + Send + Sync + 'static,
+    {
+        let tasks = self.spawn_fetches(gitlab, pipelines).await;
+        let mut pips: Vec<Pipeline> = join_all(tasks)
+            .await
+            .into_iter()
+            .filter_map(fetched)
+            .collect();
+
+        self.annotate(gitlab, &mut pips, &mut HashMap::new()).await;
+
+        pips
+    }
+
+    /// Same as `fetch()` followed by `apply()` but prints every
+    /// pipeline (in the output format) as soon as it's fetched
+    /// - sorting is not applied. Returns printed pipelines.
+    pub async fn stream<G>(&self, gitlab: &G, pipelines: &JsonValue) -> Vec<Pipeline>
+    where
+        G: GitlabApi + Clone + Send + Sync + 'static,
+    {
+        let mut tasks = self
+            .spawn_fetches(gitlab, pipelines)
+            .await
+            .into_iter()
+            .collect::<FuturesUnordered<_>>();
+        let mut sparklines = HashMap::new();
+        let mut pips = vec![];
+
+        while let Some(pip) = tasks.next().await {
+            let Some(mut pip) = fetched(pip) else {
+                continue;
+            };
+
+            if self.failed_only && "failed" != pip.status {
+                continue;
+            }
+
+            self.annotate(gitlab, std::slice::from_mut(&mut pip), &mut sparklines)
+                .await;
+            self.apply_one(&mut pip);
+            self.print(std::slice::from_ref(&pip));
+            pips.push(pip);
+        }
+
+        pips
+    }
+
+    /// Output is printed pipeline by pipeline instead of being
+    /// rendered at once (JSON lines).
+    pub fn is_streamed(&self) -> bool {
+        self.output == "jsonl"
+    }
+
+    /// Spawns fetching of jobs (and details if needed) of each
+    /// listed pipeline.
+    async fn spawn_fetches<G>(
+        &self,
+        gitlab: &G,
+        pipelines: &JsonValue,
+    ) -> Vec<JoinHandle<gitlab::Result<Pipeline>>>
+    where
+        G: GitlabApi + Clone + Send + Sync + 'static,
+    {
+        let mut tasks = vec![];
+        let semaphore = Arc::new(Semaphore::new(SEMAPHORE_LIMIT));
+
+        // GraphQL API addresses projects by full path only -
+        // REST API is used if the project cannot be fetched.
+        let project_path = match self.graphql {
+            true => gitlab.project().await.ok().and_then(|project| {
+                project["path_with_namespace"]
+                    .as_str()
+                    .map(|p| p.to_string())
+            }),
+            false => None,
+        };
+
+        for pip in pipelines.members() {
+            let mut pip = Pipeline::from_json(pip);
+            let gitlab = gitlab.clone();
+            let view = self.clone();
+            let project_path = project_path.clone();
+
+            let semaphore = semaphore.clone();
+
+            tasks.push(tokio::spawn(async move {
+                // Acquire semaphore lock (freed once fetched).
+                let _permit = semaphore.acquire_owned().await.unwrap();
+
+                view.fetch_one(&gitlab, &mut pip, project_path.as_deref())
+                    .await
+                    .map_err(|e| format!("{}: {}", pip.id.0, e))?;
+
+                Ok::<_, gitlab::Error>(pip)
+            }));
+        }
+
+        tasks
+    }
+
+    /// Fetches jobs (and details if needed) of a single
+    /// pipeline. Project path is given for GraphQL API.
+    async fn fetch_one(
+        &self,
+        gitlab: &impl GitlabApi,
+        pip: &mut Pipeline,
+        project_path: Option<&str>,
+    ) -> gitlab::Result<()> {
+        // Fetch jobs only if they are going to be rendered.
+        if self.depth != "pipelines" {
+            // Fall back to REST API if GraphQL fails (e.g.
+            // older Gitlab).
+            let fetched = match project_path {
+                Some(path) => pip.fetch_stages_graphql(gitlab, path).await.is_ok(),
+                None => false,
+            };
+
+            if !fetched {
+                pip.fetch_stages(gitlab).await?;
+            }
+
+            if self.show_empty_stages {
+                pip.fetch_declared_stages(gitlab).await;
+            }
+        }
+
+        // Fetch details only if needed. Details also carry
+        // pipeline duration in case no jobs were fetched
+        // and CI config errors of failed pipelines without
+        // jobs.
+        if self.show_finished
+            || self.depth == "pipelines"
+            || self.output != "tree"
+            || ("failed" == pip.status && pip.stages.is_empty())
+        {
+            pip.fetch_details(gitlab).await?;
+        }
+
+        pip.fetch_merge_request(gitlab).await;
+
+        if self.show_excerpts {
+            pip.fetch_excerpts(gitlab, &self.excerpt_patterns).await;
+        }
+
+        if self.show_variables {
+            pip.fetch_variables(gitlab).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Adds sparklines (if requested) and blocked deployment
+    /// marks to fetched pipelines. Sparklines are cached by ref.
+    async fn annotate(
+        &self,
+        gitlab: &impl GitlabApi,
+        pips: &mut [Pipeline],
+        sparklines: &mut HashMap<String, Option<String>>,
+    ) {
+        if self.show_sparkline {
+            self.fetch_sparklines(gitlab, pips, sparklines).await;
+        }
+
+        // Annotations are optional - the tree is shown anyway.
+        let _ = deployments::mark_blocked(gitlab, pips).await;
+    }
+
+    /// Fetches duration sparklines - once per ref.
+    async fn fetch_sparklines(
+        &self,
+        gitlab: &impl GitlabApi,
+        pips: &mut [Pipeline],
+        sparklines: &mut HashMap<String, Option<String>>,
+    ) {
+        let mut refs = pips
+            .iter()
+            .map(|p| p.git_ref.clone())
+            .filter(|r| !sparklines.contains_key(r))
+            .collect::<Vec<_>>();
+        refs.sort();
+        refs.dedup();
+
+        let durations = join_all(refs.iter().map(|r| sparkline::durations(gitlab, r))).await;
+
+        for (r, durations) in refs.into_iter().zip(durations) {
+            sparklines.insert(r, durations.ok().map(|d| sparkline::render(&d)));
+        }
+
+        for pip in pips.iter_mut() {
+            pip.sparkline = sparklines.get(&pip.git_ref).cloned().flatten();
+        }
+    }
+
+    /// Applies client-side sorting and filters.
+    pub fn apply(&self, pips: &mut Vec<Pipeline>) {
+        // Longest pipelines go first.
+        if self.sort == "duration" {
+            pips.sort_by_key(|p| std::cmp::Reverse(p.duration()));
+        }
+
+        if self.reverse {
+            pips.reverse();
+        }
+
+        if self.failed_only {
+            pips.retain(|p| "failed" == p.status);
+        }
+
+        for pip in pips.iter_mut() {
+            self.apply_one(pip);
+        }
+    }
+
+    /// Applies client-side filters to a single pipeline.
+    fn apply_one(&self, pip: &mut Pipeline) {
+        if let Some(by) = &self.sort_jobs {
+            pip.sort_jobs(by);
+        }
+        if let Some(names) = &self.stage_names {
+            pip.retain_stages(names);
+        }
+        if let Some(pattern) = &self.job_pattern {
+            pip.retain_jobs(pattern);
+        }
+        if self.failed_only {
+            pip.retain_failed();
+        }
+    }
+
+    /// Picks renderer according to the output format.
+    pub fn renderer(&self) -> Box<dyn Render> {
+        match self.output.as_str() {
+            "csv" => Box::new(output::Csv {
+                jobs: self.job_rows,
+            }),
+            "json" => Box::new(output::Json),
+            "jsonl" => Box::new(output::Jsonl),
+            "yaml" => Box::new(output::Yaml),
+            "table" => Box::new(output::Table),
+            "compact" => Box::new(output::Compact),
+            _ => Box::new(output::Tree {
+                print_config: self.print_config.clone(),
+                secret_patterns: self.secret_patterns.clone(),
+                options: output::TreeOptions {
+                    show_finished: self.show_finished,
+                    collapse_success: self.collapse_success,
+                    show_urls: self.show_urls,
+                    id_format: self.id_format.clone(),
+                },
+            }),
+        }
+    }
+
+    /// Prints pipeline trees (or pipelines in other output
+    /// format).
+    pub fn print(&self, pips: &[Pipeline]) {
+        print!("{}", self.render(pips));
+    }
+
+    /// Renders pipelines into a string in the output format.
+    pub fn render(&self, pips: &[Pipeline]) -> String {
+        self.renderer().render(pips)
+    }
+}
+
+/// Unwraps pipeline of finished fetching task - failed ones
+/// are reported and skipped.
+fn fetched(task: Result<gitlab::Result<Pipeline>, JoinError>) -> Option<Pipeline> {
+    let error = match task {
+        Ok(Ok(pip)) => return Some(pip),
+        Ok(Err(e)) => e.to_string(),
+        Err(e) => e.to_string(),
+    };
+
+    eprintln!(
+        "{}",
+        format!("Warning: cannot fetch pipeline {}", error).yellow()
+    );
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::args;
+    use crate::gitlab::fake::FakeGitlab;
+
+    #[tokio::test]
+    async fn fetch_builds_trees_from_api() {
+        let args = args::command().get_matches_from(["glp"]);
+        let view = View::new(&args, &Config::default(), None);
+        let mut gitlab = FakeGitlab {
+            pipelines: vec![
+                json::object! { id: 2, iid: 2, ref: "main", sha: "def", status: "running", web_url: "" },
+                json::object! { id: 1, iid: 1, ref: "main", sha: "abc", status: "success", web_url: "" },
+            ],
+            ..FakeGitlab::default()
+        };
+        gitlab.jobs.insert(
+            "2".to_string(),
+            json::array![{
+                id: 21, name: "build", stage: "build", status: "running",
+                web_url: "", started_at: "2024-05-12T14:00:00Z", duration: null,
+            }],
+        );
+
+        let listing = view.list(&gitlab, 5).await.unwrap();
+        let pips = view.fetch(&gitlab, &listing).await;
+
+        assert_eq!(
+            vec!["2", "1"],
+            pips.iter().map(|p| p.id.0.as_str()).collect::<Vec<_>>()
+        );
+        assert_eq!(1, pips[0].stages.len());
+        assert!(pips[1].stages.is_empty());
+    }
+
+    #[tokio::test]
+    async fn graphql_failure_falls_back_to_rest() {
+        let args = args::command().get_matches_from(["glp", "--graphql"]);
+        let view = View::new(&args, &Config::default(), None);
+        let mut gitlab = FakeGitlab {
+            pipelines: vec![
+                json::object! { id: 1, iid: 1, ref: "main", sha: "abc", status: "running", web_url: "" },
+            ],
+            ..FakeGitlab::default()
+        };
+        gitlab.jobs.insert(
+            "1".to_string(),
+            json::array![{
+                id: 11, name: "build", stage: "build", status: "running",
+                web_url: "", started_at: "2024-05-12T14:00:00Z", duration: null,
+            }],
+        );
+
+        let pips = view
+            .fetch(&gitlab, &json::from(gitlab.pipelines.clone()))
+            .await;
+
+        assert_eq!(1, pips[0].stages.len());
+    }
+
+    #[tokio::test]
+    async fn stream_returns_printed_pipelines() {
+        let args = args::command().get_matches_from(["glp", "-o", "jsonl"]);
+        let view = View::new(&args, &Config::default(), None);
+        let gitlab = FakeGitlab {
+            pipelines: vec![
+                json::object! { id: 2, iid: 2, ref: "main", sha: "def", status: "running", web_url: "" },
+                json::object! { id: 1, iid: 1, ref: "main", sha: "abc", status: "failed", web_url: "" },
+            ],
+            ..FakeGitlab::default()
+        };
+
+        let listing = json::from(gitlab.pipelines.clone());
+        let mut ids = view
+            .stream(&gitlab, &listing)
+            .await
+            .iter()
+            .map(|p| p.id.0.clone())
+            .collect::<Vec<_>>();
+        ids.sort();
+
+        assert_eq!(vec!["1", "2"], ids);
+        assert_eq!(2, view.fetch(&gitlab, &listing).await.len());
+    }
+
+    #[test]
+    fn reverse_is_applied_to_fetched_pipelines() {
+        let args = args::command().get_matches_from(["glp", "--reverse"]);
+        let view = View::new(&args, &Config::default(), None);
+        let mut pips = [2, 1]
+            .map(|id| {
+                Pipeline::from_json(
+                    &json::object! { id: id, ref: "main", sha: "abc", status: "success", web_url: "" },
+                )
+            })
+            .to_vec();
+
+        view.apply(&mut pips);
+
+        assert_eq!(
+            vec!["1", "2"],
+            pips.iter().map(|p| p.id.0.as_str()).collect::<Vec<_>>()
+        );
+    }
+
+    #[tokio::test]
+    async fn pipelines_failing_to_fetch_are_skipped() {
+        // Details are fetched for non-tree outputs.
+        let args = args::command().get_matches_from(["glp", "-o", "csv"]);
+        let view = View::new(&args, &Config::default(), None);
+        let gitlab = FakeGitlab {
+            pipelines: vec![
+                json::object! { id: 1, iid: 1, ref: "main", sha: "abc", status: "success", web_url: "" },
+            ],
+            ..FakeGitlab::default()
+        };
+        let listing = json::array![
+            { id: 2, iid: 2, ref: "main", sha: "def", status: "success", web_url: "" },
+            { id: 1, iid: 1, ref: "main", sha: "abc", status: "success", web_url: "" },
+        ];
+
+        let pips = view.fetch(&gitlab, &listing).await;
+
+        assert_eq!(
+            vec!["1"],
+            pips.iter().map(|p| p.id.0.as_str()).collect::<Vec<_>>()
+        );
+    }
+}
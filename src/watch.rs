@@ -0,0 +1,287 @@
+use crate::config::{Config, HooksConfig};
+use crate::exit;
+use crate::gitlab::{self, Gitlab};
+use crate::hooks::StatusHook;
+use crate::notify::Notifier;
+use crate::view::View;
+use chrono::Local;
+use colored::*;
+use humantime::parse_duration;
+use regex::Regex;
+use std::io::{self, Write};
+use std::time::{Duration, Instant};
+
+const FOLLOW_INTERVAL: Duration = Duration::from_secs(5);
+/// Default maximum interval when nothing is running.
+const MAX_INTERVAL: Duration = Duration::from_secs(300);
+
+/// Pipeline in one of these states won't change anymore.
+pub fn is_terminal(status: &str) -> bool {
+    matches!(status, "success" | "failed" | "canceled" | "skipped")
+}
+
+/// Pipeline in one of these states won't change on its own -
+/// it's either terminal or blocked by a manual job (`manual`)
+/// or waiting for a delayed job (`scheduled`).
+fn is_stopped(status: &str) -> bool {
+    is_terminal(status) || matches!(status, "manual" | "scheduled")
+}
+
+/// Last drawn frame - only changed lines are redrawn.
+#[derive(Default)]
+struct Screen {
+    lines: Vec<String>,
+    drawn: bool,
+    /// Output is appended instead of redrawn (streamed JSON
+    /// lines must not be mixed with escape codes).
+    append: bool,
+}
+
+impl Screen {
+    fn new(view: &View) -> Screen {
+        Screen {
+            append: view.is_streamed(),
+            ..Screen::default()
+        }
+    }
+
+    /// Draws given output over the previous one.
+    fn draw(&mut self, output: &str) {
+        if self.append {
+            print!("{}", output);
+            io::stdout().flush().unwrap();
+            return;
+        }
+
+        let lines = output.lines().map(|l| l.to_string()).collect::<Vec<_>>();
+        let mut frame = String::new();
+
+        // Clear screen and move cursor to the top left corner.
+        if !self.drawn {
+            frame.push_str("\x1b[2J\x1b[H");
+            self.drawn = true;
+        }
+
+        for (i, line) in lines.iter().enumerate() {
+            if self.lines.get(i) != Some(line) {
+                // Move to the line start, rewrite it and clear
+                // the rest of it.
+                frame.push_str(&format!("\x1b[{};1H{}\x1b[K", i + 1, line));
+            }
+        }
+
+        // Clear leftovers of longer previous frame.
+        if lines.len() < self.lines.len() {
+            frame.push_str(&format!("\x1b[{};1H\x1b[J", lines.len() + 1));
+        }
+
+        // Park cursor below the output.
+        frame.push_str(&format!("\x1b[{};1H", lines.len() + 1));
+        print!("{}", frame);
+        io::stdout().flush().unwrap();
+
+        self.lines = lines;
+    }
+}
+
+/// Pipeline in one of these states is going to change soon.
+pub fn is_active(status: &str) -> bool {
+    matches!(
+        status,
+        "created" | "waiting_for_resource" | "preparing" | "pending" | "running"
+    )
+}
+
+/// Computes next polling interval - the shortest one while any
+/// pipeline is active, otherwise it doubles up to the maximum.
+fn next_interval(current: Duration, active: bool, max: Duration) -> Duration {
+    match active {
+        true => FOLLOW_INTERVAL,
+        false => (current * 2).min(max),
+    }
+}
+
+/// Watch mode params.
+pub struct Options {
+    /// Take over the terminal (alternate screen with status
+    /// bar).
+    pub fullscreen: bool,
+    /// Alert on status changes (according to `[notify]`
+    /// rules).
+    pub notify: bool,
+    /// Stop watching after this time - fails if any pipeline
+    /// is still active.
+    pub timeout: Option<Duration>,
+}
+
+/// Redraws listing of recent pipelines until interrupted.
+/// Polls frequently while any pipeline is active and backs
+/// off when everything is finished.
+/// In fullscreen mode the terminal alternate screen is used
+/// (restored on exit) with a status bar at the bottom.
+pub async fn watch(
+    gitlab: &Gitlab,
+    view: &View,
+    limit: u8,
+    config: &Config,
+    options: &Options,
+) -> gitlab::Result<()> {
+    // Streamed output is not drawn at all.
+    if !options.fullscreen || view.is_streamed() {
+        return watch_loop(gitlab, view, limit, config, options).await;
+    }
+
+    // Switch to alternate screen and hide cursor.
+    print!("\x1b[?1049h\x1b[?25l");
+
+    let result = tokio::select! {
+        result = watch_loop(gitlab, view, limit, config, options) => result,
+        _ = tokio::signal::ctrl_c() => Ok(()),
+    };
+
+    // Show cursor and switch back to main screen.
+    print!("\x1b[?25h\x1b[?1049l");
+    io::stdout().flush().unwrap();
+
+    result
+}
+
+async fn watch_loop(
+    gitlab: &Gitlab,
+    view: &View,
+    limit: u8,
+    config: &Config,
+    options: &Options,
+) -> gitlab::Result<()> {
+    let max_interval = match &config.watch.max_interval {
+        Some(max) => parse_duration(max).map_err(|e| format!("Invalid max_interval: {}", e))?,
+        None => MAX_INTERVAL,
+    };
+    let deadline = options.timeout.map(|timeout| Instant::now() + timeout);
+    let mut notifier = options.notify.then(|| Notifier::new(&config.notify));
+    let mut hook = StatusHook::new(&config.hooks);
+    let mut interval = FOLLOW_INTERVAL;
+    let mut screen = Screen::new(view);
+    let fullscreen = options.fullscreen && !view.is_streamed();
+
+    loop {
+        let pipelines = view.list(gitlab, limit).await?;
+        let mut pips = view.fetch(gitlab, &pipelines).await;
+        view.apply(&mut pips);
+
+        if let Some(notifier) = &mut notifier {
+            notifier.update(&pips);
+        }
+        hook.update(&pips);
+
+        let output = view.render(&pips);
+        let refreshed_at = Local::now().format("%H:%M:%S");
+        let active = pips.iter().any(|p| is_active(&p.status));
+
+        if deadline.is_some_and(|d| Instant::now() >= d) {
+            screen.draw(&output);
+
+            return match active {
+                true => Err(exit::timeout("Pipelines didn't finish in time.")),
+                false => Ok(()),
+            };
+        }
+
+        // Never sleep past the deadline.
+        interval = next_interval(interval, active, max_interval);
+        let sleep = match deadline {
+            Some(d) => interval.min(d.saturating_duration_since(Instant::now())),
+            None => interval,
+        };
+
+        if !fullscreen {
+            screen.draw(&output);
+            tokio::time::sleep(sleep).await;
+            continue;
+        }
+
+        // Redraw the status bar every second.
+        for remaining in (1..=sleep.as_secs()).rev() {
+            screen.draw(&format!(
+                "{}\n{}",
+                output,
+                format!(
+                    "Last refresh {}, next in {}s (Ctrl-C to quit)",
+                    refreshed_at, remaining
+                )
+                .reversed()
+            ));
+            tokio::time::sleep(Duration::from_secs(1)).await;
+        }
+        tokio::time::sleep(sleep - Duration::from_secs(sleep.as_secs())).await;
+    }
+}
+
+/// Redraws pipeline tree periodically until the pipeline
+/// (or its jobs matching given pattern) stops - see
+/// `is_stopped()`. Returns the final status - the worst one
+/// of the matching jobs.
+pub async fn follow(
+    gitlab: &Gitlab,
+    view: &View,
+    pipeline_id: &str,
+    job: Option<&Regex>,
+    hooks: &HooksConfig,
+) -> gitlab::Result<String> {
+    let mut screen = Screen::new(view);
+    let mut hook = StatusHook::new(hooks);
+
+    loop {
+        let pipeline = gitlab.get(&format!("pipelines/{}", pipeline_id)).await?;
+        let mut pips = view.fetch(gitlab, &json::array![pipeline]).await;
+
+        // Looked up before filters are applied.
+        let job_status = job.map(|pattern| {
+            jobs_status(
+                pips.iter()
+                    .flat_map(|p| p.stages.iter())
+                    .flat_map(|s| s.jobs.iter())
+                    .filter(|j| pattern.is_match(&j.name.0))
+                    .map(|j| j.status.as_str())
+                    .collect(),
+            )
+        });
+
+        view.apply(&mut pips);
+        hook.update(&pips);
+
+        screen.draw(&view.render(&pips));
+
+        match (job_status, pips.first()) {
+            (Some(Some(status)), _) => return Ok(status),
+            (Some(None), Some(pip)) if is_stopped(&pip.status) => {
+                return Err(format!(
+                    "No job matching \"{}\" in pipeline {}.",
+                    job.unwrap(),
+                    pipeline_id
+                )
+                .into())
+            }
+            (None, Some(pip)) if is_stopped(&pip.status) => return Ok(pip.status.clone()),
+            _ => {}
+        }
+
+        tokio::time::sleep(FOLLOW_INTERVAL).await;
+    }
+}
+
+/// Combines statuses of jobs - `None` while any of them is
+/// going to change (or there are none). Manual jobs won't
+/// finish on their own so they count as finished.
+fn jobs_status(statuses: Vec<&str>) -> Option<String> {
+    if statuses.is_empty() || statuses.iter().any(|s| !is_terminal(s) && "manual" != *s) {
+        return None;
+    }
+
+    statuses
+        .iter()
+        .find(|s| "failed" == **s)
+        .or_else(|| statuses.iter().find(|s| "success" != **s))
+        .or(statuses.first())
+        .map(|s| s.to_string())
+}